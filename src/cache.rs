@@ -0,0 +1,608 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use http::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LINK};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use http_body_util::{BodyExt, Full};
+use octocrab::FromResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{self, McpGithubError};
+use crate::ratelimit;
+
+/// Maximum number of responses kept in memory before the oldest is evicted.
+const MAX_ENTRIES: usize = 1024;
+
+/// Conditional-request cache for GitHub REST GETs, keyed by route within a
+/// scope naming the API host and credential (see [`Self::with_scope`]).
+///
+/// Responses carrying an `ETag` or `Last-Modified` header are stored. Within
+/// the TTL a stored response is returned without touching the network; after
+/// that it is revalidated with `If-None-Match`/`If-Modified-Since`, and a
+/// `304 Not Modified` (which GitHub does not count against the rate limit)
+/// reuses the stored body.
+pub struct ResponseCache {
+    ttl: Duration,
+    dir: Option<PathBuf>,
+    scope: String,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    hits: AtomicU64,
+    revalidated: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    scope: String,
+    route: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    link: Option<String>,
+    body: String,
+    stored_at: u64,
+}
+
+/// Snapshot of cache counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    /// Served from cache without a request.
+    pub hits: u64,
+    /// Served from cache after a `304 Not Modified`.
+    pub revalidated: u64,
+    /// Fetched in full from GitHub.
+    pub misses: u64,
+    /// Responses currently held in memory.
+    pub entries: usize,
+}
+
+impl ResponseCache {
+    /// Create a cache. A zero `ttl` revalidates on every call; `dir` also
+    /// persists responses to disk so they survive restarts.
+    pub fn new(ttl: Duration, dir: Option<PathBuf>) -> Self {
+        Self {
+            ttl,
+            dir,
+            scope: String::new(),
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            revalidated: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Keep entries apart per GitHub host and identity, so a response is
+    /// never served to a client that may not be allowed to see it. Matters
+    /// when a cache directory is shared; see `ClientOptions::cache_scope`.
+    pub fn with_scope(mut self, scope: String) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            revalidated: self.revalidated.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+
+    /// GET `route`, answering from the cache where possible. Rate-limit
+    /// failures are reported as their dedicated error variants.
+    pub async fn get<R: FromResponse>(
+        &self,
+        github: &octocrab::Octocrab,
+        route: &str,
+    ) -> Result<R, McpGithubError> {
+        let cached = match self.lookup(route) {
+            Some(entry) => Some(entry),
+            None => self.load_from_disk(route).await,
+        };
+
+        if let Some(ref entry) = cached {
+            if self.is_fresh(entry) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(route, "cache hit");
+                return Ok(R::from_response(entry.to_response()).await?);
+            }
+        }
+
+        let mut headers = HeaderMap::new();
+        if let Some(ref entry) = cached {
+            if let Some(value) = entry.etag.as_deref().and_then(header_value) {
+                headers.insert(IF_NONE_MATCH, value);
+            }
+            if let Some(value) = entry.last_modified.as_deref().and_then(header_value) {
+                headers.insert(IF_MODIFIED_SINCE, value);
+            }
+        }
+
+        let response = github._get_with_headers(route, Some(headers)).await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(mut entry) = cached {
+                self.revalidated.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(route, "cache revalidated");
+                entry.stored_at = now_secs();
+                let response = entry.to_response();
+                self.store(entry).await;
+                return Ok(R::from_response(response).await?);
+            }
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(failure(route, status, response).await);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        tracing::debug!(route, "cache miss");
+
+        let (parts, body) = response.into_parts();
+        let body = body.collect().await?.to_bytes();
+        let header = |name| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|v: &HeaderValue| v.to_str().ok())
+                .map(String::from)
+        };
+        let entry = CacheEntry {
+            scope: self.scope.clone(),
+            route: route.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            link: header(LINK),
+            body: String::from_utf8_lossy(&body).into_owned(),
+            stored_at: now_secs(),
+        };
+        let response = entry.to_response();
+        if entry.etag.is_some() || entry.last_modified.is_some() {
+            self.store(entry).await;
+        }
+        Ok(R::from_response(response).await?)
+    }
+
+    /// Forget every entry for `prefix` and the routes below it (a `/` or
+    /// `?` follows the prefix), in memory and on disk. Called after a write
+    /// so later reads ask GitHub again.
+    pub async fn invalidate_prefix(&self, prefix: &str) {
+        let under = |route: &str| {
+            route
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?']))
+        };
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|route, _| !under(route));
+
+        let Some(ref dir) = self.dir else {
+            return;
+        };
+        let Ok(mut files) = tokio::fs::read_dir(dir).await else {
+            return;
+        };
+        while let Ok(Some(file)) = files.next_entry().await {
+            let path = file.path();
+            let Ok(bytes) = tokio::fs::read(&path).await else {
+                continue;
+            };
+            let Ok(entry) = serde_json::from_slice::<CacheEntry>(&bytes) else {
+                continue;
+            };
+            if entry.scope == self.scope && under(&entry.route) {
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    tracing::warn!(path = %path.display(), error = %e, "Failed to remove cache entry");
+                }
+            }
+        }
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        now_secs().saturating_sub(entry.stored_at) < self.ttl.as_secs()
+    }
+
+    fn lookup(&self, route: &str) -> Option<CacheEntry> {
+        self.entries.lock().unwrap().get(route).cloned()
+    }
+
+    /// Path of the disk entry for `route` in this cache's scope.
+    fn file_path(&self, dir: &Path, route: &str) -> PathBuf {
+        dir.join(cache_file_name(&format!("{}\n{}", self.scope, route)))
+    }
+
+    async fn store(&self, entry: CacheEntry) {
+        if let Some(ref dir) = self.dir {
+            let path = self.file_path(dir, &entry.route);
+            let result = async {
+                create_private_dir(dir).await?;
+                let json = serde_json::to_vec(&entry)?;
+                write_private_file(&path, &json).await
+            }
+            .await;
+            if let Err(e) = result {
+                tracing::warn!(path = %path.display(), error = %e, "Failed to write cache entry");
+            }
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&entry.route) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, e)| e.stored_at)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(entry.route.clone(), entry);
+    }
+
+    async fn load_from_disk(&self, route: &str) -> Option<CacheEntry> {
+        let dir = self.dir.as_ref()?;
+        let bytes = tokio::fs::read(self.file_path(dir, route)).await.ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&bytes).ok()?;
+        // Guard against hash collisions between routes and scopes
+        if entry.route != route || entry.scope != self.scope {
+            return None;
+        }
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(route.to_string(), entry.clone());
+        Some(entry)
+    }
+}
+
+impl CacheEntry {
+    /// Rebuild an HTTP response so octocrab's own decoding (including
+    /// `Link` header pagination) applies to cached bodies.
+    fn to_response(
+        &self,
+    ) -> http::Response<impl http_body::Body<Data = Bytes, Error = octocrab::Error> + Send> {
+        let mut builder = http::Response::builder().status(StatusCode::OK);
+        if let Some(link) = self.link.as_deref().and_then(header_value) {
+            builder = builder.header(LINK, link);
+        }
+        let body = Full::new(Bytes::from(self.body.clone())).map_err(|never| match never {});
+        builder.body(body).expect("valid cached response")
+    }
+}
+
+/// Turn an unsuccessful response into an error, recognising rate limits
+/// and missing OAuth scopes from the headers before octocrab's error
+/// mapping discards them.
+pub(crate) async fn failure(
+    route: &str,
+    status: StatusCode,
+    response: http::Response<http_body_util::combinators::BoxBody<Bytes, octocrab::Error>>,
+) -> McpGithubError {
+    let headers = response.headers().clone();
+    let err = match octocrab::map_github_error(response).await {
+        Err(e) => e,
+        Ok(_) => return McpGithubError::Other(format!("GitHub returned {}", status)),
+    };
+    let message = match &err {
+        octocrab::Error::GitHub { source, .. } => source.message.as_str(),
+        _ => "",
+    };
+    match ratelimit::classify_response(status, &headers, message) {
+        Some(throttled) => throttled,
+        None => McpGithubError::from_github(err, Some(&headers), &error::route_target(route)),
+    }
+}
+
+/// Create the cache directory readable by the current user only. An
+/// existing directory is left as it is.
+async fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = tokio::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    builder.mode(0o700);
+    builder.create(dir).await
+}
+
+/// Write a cache file readable by the current user only; cached bodies can
+/// hold private repository content.
+async fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    // Files written by older versions may have been created more permissive
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .await?;
+    }
+    file.write_all(contents).await?;
+    file.flush().await
+}
+
+fn header_value(value: &str) -> Option<HeaderValue> {
+    HeaderValue::from_str(value).ok()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Stable file name for a route (FNV-1a, so it doesn't change between builds).
+fn cache_file_name(route: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in route.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}.json", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn github_for(server: &MockServer) -> octocrab::Octocrab {
+        octocrab::OctocrabBuilder::new()
+            .base_uri(server.uri())
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    async fn mock_repo_with_etag(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_json(serde_json::json!({"name": "r"})),
+            )
+            .with_priority(2)
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn test_cache_file_name_is_stable() {
+        assert_eq!(cache_file_name(""), "cbf29ce484222325.json");
+        assert_ne!(cache_file_name("/repos/a/b"), cache_file_name("/repos/a/c"));
+    }
+
+    #[tokio::test]
+    async fn test_cached_response_preserves_link_header() {
+        let entry = CacheEntry {
+            scope: String::new(),
+            route: "/repos/o/r/tags".to_string(),
+            etag: Some("\"x\"".to_string()),
+            last_modified: None,
+            link: Some("<https://api.github.com/repos/o/r/tags?page=2>; rel=\"next\"".to_string()),
+            body: "[1, 2]".to_string(),
+            stored_at: 0,
+        };
+        let page: octocrab::Page<u32> = octocrab::Page::from_response(entry.to_response())
+            .await
+            .unwrap();
+        assert_eq!(page.items, vec![1, 2]);
+        assert!(page.next.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_revalidates_with_etag() {
+        let server = MockServer::start().await;
+        mock_repo_with_etag(&server).await;
+        let github = github_for(&server);
+        let cache = ResponseCache::new(Duration::ZERO, None);
+
+        let first: serde_json::Value = cache.get(&github, "/repos/o/r").await.unwrap();
+        let second: serde_json::Value = cache.get(&github, "/repos/o/r").await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 0,
+                revalidated: 1,
+                misses: 1,
+                entries: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fresh_entry_skips_request() {
+        let server = MockServer::start().await;
+        mock_repo_with_etag(&server).await;
+        let github = github_for(&server);
+        let cache = ResponseCache::new(Duration::from_secs(300), None);
+
+        let _: serde_json::Value = cache.get(&github, "/repos/o/r").await.unwrap();
+        let _: serde_json::Value = cache.get(&github, "/repos/o/r").await.unwrap();
+
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 1);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_responses_without_validators_are_not_cached() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&server)
+            .await;
+        let github = github_for(&server);
+        let cache = ResponseCache::new(Duration::from_secs(300), None);
+
+        let _: serde_json::Value = cache.get(&github, "/repos/o/r").await.unwrap();
+        let _: serde_json::Value = cache.get(&github, "/repos/o/r").await.unwrap();
+
+        assert_eq!(cache.stats().misses, 2);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/missing"))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_json(serde_json::json!({"message": "Not Found"})),
+            )
+            .mount(&server)
+            .await;
+        let github = github_for(&server);
+        let cache = ResponseCache::new(Duration::from_secs(300), None);
+
+        let result: Result<serde_json::Value, _> = cache.get(&github, "/repos/o/missing").await;
+        assert!(matches!(result, Err(McpGithubError::RepoNotFound(_))));
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_failure_is_classified() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", "1700000000")
+                    .set_body_json(serde_json::json!({"message": "API rate limit exceeded"})),
+            )
+            .mount(&server)
+            .await;
+        let github = github_for(&server);
+        let cache = ResponseCache::new(Duration::ZERO, None);
+
+        let result: Result<serde_json::Value, _> = cache.get(&github, "/repos/o/r").await;
+        match result {
+            Err(McpGithubError::RateLimited { reset_at }) => {
+                assert_eq!(reset_at.timestamp(), 1_700_000_000)
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_survives_new_instance() {
+        let server = MockServer::start().await;
+        mock_repo_with_etag(&server).await;
+        let github = github_for(&server);
+        let dir = std::env::temp_dir().join(format!("mcp-github-cache-test-{}", std::process::id()));
+
+        let cache = ResponseCache::new(Duration::ZERO, Some(dir.clone()));
+        let _: serde_json::Value = cache.get(&github, "/repos/o/r").await.unwrap();
+
+        let reloaded = ResponseCache::new(Duration::ZERO, Some(dir.clone()));
+        let value: serde_json::Value = reloaded.get(&github, "/repos/o/r").await.unwrap();
+        assert_eq!(value["name"], "r");
+        assert_eq!(reloaded.stats().revalidated, 1);
+        assert_eq!(reloaded.stats().misses, 0);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_prefix_drops_routes_below_it() {
+        let server = MockServer::start().await;
+        for route in [
+            "/repos/o/r/issues",
+            "/repos/o/r/issues/5",
+            "/repos/o/r/issuesx",
+        ] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("etag", "\"v1\"")
+                        .set_body_json(serde_json::json!([])),
+                )
+                .mount(&server)
+                .await;
+        }
+        let github = github_for(&server);
+        let dir =
+            std::env::temp_dir().join(format!("mcp-github-cache-inval-{}", std::process::id()));
+        let cache = ResponseCache::new(Duration::from_secs(300), Some(dir.clone()));
+        for route in [
+            "/repos/o/r/issues?state=open",
+            "/repos/o/r/issues/5",
+            "/repos/o/r/issuesx",
+        ] {
+            let _: serde_json::Value = cache.get(&github, route).await.unwrap();
+        }
+
+        cache.invalidate_prefix("/repos/o/r/issues").await;
+        assert_eq!(cache.stats().entries, 1);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let reloaded = ResponseCache::new(Duration::from_secs(300), Some(dir.clone()));
+        let _: serde_json::Value = reloaded.get(&github, "/repos/o/r/issuesx").await.unwrap();
+        let _: serde_json::Value = reloaded.get(&github, "/repos/o/r/issues/5").await.unwrap();
+        assert_eq!(reloaded.stats().hits, 1);
+        assert_eq!(reloaded.stats().misses, 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_is_private_to_its_scope() {
+        let server = MockServer::start().await;
+        mock_repo_with_etag(&server).await;
+        let github = github_for(&server);
+        let dir = std::env::temp_dir()
+            .join(format!("mcp-github-cache-scope-{}", std::process::id()))
+            .join("cache");
+        let ttl = Duration::from_secs(300);
+
+        let cache = ResponseCache::new(ttl, Some(dir.clone())).with_scope("a".to_string());
+        let _: serde_json::Value = cache.get(&github, "/repos/o/r").await.unwrap();
+
+        // A fresh entry is reused within its scope without a request...
+        let same = ResponseCache::new(ttl, Some(dir.clone())).with_scope("a".to_string());
+        let _: serde_json::Value = same.get(&github, "/repos/o/r").await.unwrap();
+        assert_eq!(same.stats().hits, 1);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        // ...but another host or credential fetches its own copy
+        let other = ResponseCache::new(ttl, Some(dir.clone())).with_scope("b".to_string());
+        let _: serde_json::Value = other.get(&github, "/repos/o/r").await.unwrap();
+        assert_eq!(other.stats().hits, 0);
+        assert_eq!(other.stats().misses, 1);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&dir), 0o700);
+            for file in std::fs::read_dir(&dir).unwrap() {
+                assert_eq!(mode(&file.unwrap().path()), 0o600);
+            }
+        }
+
+        let _ = std::fs::remove_dir_all(dir.parent().unwrap());
+    }
+}
@@ -0,0 +1,750 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use http::header::USER_AGENT;
+use http::{HeaderValue, Request, StatusCode, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::HttpConnector;
+use octocrab::auth::AppAuth;
+use octocrab::models::{AppId, InstallationId};
+use octocrab::service::middleware::auth_header::AuthHeaderLayer;
+use octocrab::service::middleware::extra_headers::ExtraHeadersLayer;
+use octocrab::{AuthState, Octocrab, OctocrabBuilder};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use tower::Service;
+use tower_http::follow_redirect::FollowRedirect;
+
+use crate::error::McpGithubError;
+
+/// REST API root of github.com.
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Upload API root of github.com.
+pub const DEFAULT_UPLOAD_URL: &str = "https://uploads.github.com";
+
+/// How the server authenticates to GitHub.
+#[derive(Clone, Default)]
+pub enum Credentials {
+    /// No authentication (60 requests/hour).
+    #[default]
+    Anonymous,
+    /// Personal access token sent as a bearer token.
+    Token(String),
+    /// A GitHub App acting through one of its installations.
+    App(AppCredentials),
+}
+
+/// GitHub App identity. Installation tokens are minted from the private key
+/// and renewed by octocrab shortly before they expire, so long-running
+/// sessions keep working without a restart.
+#[derive(Clone)]
+pub struct AppCredentials {
+    pub app_id: u64,
+    /// PEM file holding the app's RSA private key.
+    pub private_key: PathBuf,
+    /// Installation to act as. Looked up from `owner` when not given.
+    pub installation_id: Option<u64>,
+    /// Org or user whose installation is used when `installation_id` is unset.
+    pub owner: Option<String>,
+}
+
+/// Where and how to reach the GitHub API.
+#[derive(Clone, Default)]
+pub struct ClientOptions {
+    pub credentials: Credentials,
+    /// REST API root, e.g. `https://ghe.example.com/api/v3` for GitHub
+    /// Enterprise Server. Defaults to [`DEFAULT_API_URL`].
+    pub api_url: Option<String>,
+    /// Upload API root. Derived from `api_url` when not given.
+    pub upload_url: Option<String>,
+    /// PEM file with extra CA certificates to trust on top of the system roots.
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl ClientOptions {
+    /// The REST API root requests are sent to.
+    pub fn api_url(&self) -> &str {
+        self.api_url.as_deref().unwrap_or(DEFAULT_API_URL)
+    }
+
+    /// Identifies the API host and credential for the response cache, so a
+    /// shared cache directory never serves one identity's responses to
+    /// another. Credentials only appear as a SHA-256 digest.
+    pub fn cache_scope(&self) -> String {
+        let identity = match self.credentials {
+            Credentials::Anonymous => "anonymous".to_string(),
+            Credentials::Token(ref token) => format!("token:{}", token),
+            Credentials::App(ref app) => format!(
+                "app:{}:{}:{}",
+                app.app_id,
+                app.installation_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+                app.owner.as_deref().unwrap_or_default()
+            ),
+        };
+        let digest = ring::digest::digest(&ring::digest::SHA256, identity.as_bytes());
+        let hex: String = digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{} {}", self.api_url().trim_end_matches('/'), hex)
+    }
+
+    /// The GraphQL endpoint. GitHub Enterprise Server serves it at
+    /// `/api/graphql` beside the `/api/v3` REST root; elsewhere it is
+    /// `/graphql` under the API root.
+    pub fn graphql_url(&self) -> String {
+        let api = self.api_url().trim_end_matches('/');
+        format!("{}/graphql", api.strip_suffix("/v3").unwrap_or(api))
+    }
+
+    /// The upload API root. GitHub Enterprise Server serves uploads from
+    /// `/api/uploads` next to `/api/v3`; other custom hosts fall back to the
+    /// API root itself.
+    pub fn upload_url(&self) -> String {
+        if let Some(ref url) = self.upload_url {
+            return url.clone();
+        }
+        match self.api_url {
+            None => DEFAULT_UPLOAD_URL.to_string(),
+            Some(ref api) => {
+                let api = api.trim_end_matches('/');
+                match api.strip_suffix("/api/v3") {
+                    Some(host) => format!("{}/api/uploads", host),
+                    None => api.to_string(),
+                }
+            }
+        }
+    }
+}
+
+/// Build the GitHub client described by `options`. For GitHub App
+/// credentials this resolves the installation and mints its first token, so
+/// a bad key or missing installation is reported up front.
+pub async fn build(options: &ClientOptions) -> Result<Octocrab, McpGithubError> {
+    let github = build_client(options)?;
+    match options.credentials {
+        Credentials::App(ref app) => installation_client(github, app).await,
+        _ => Ok(github),
+    }
+}
+
+fn build_client(options: &ClientOptions) -> Result<Octocrab, McpGithubError> {
+    let api_url = parse_url(options.api_url(), "api-url")?;
+    let upload_url = parse_url(&options.upload_url(), "upload-url")?;
+    let app_auth = match options.credentials {
+        Credentials::App(ref app) => Some(AppAuth {
+            app_id: AppId(app.app_id),
+            key: load_app_key(&app.private_key)?,
+        }),
+        _ => None,
+    };
+
+    // Assemble octocrab's default middleware stack (timeouts, retries,
+    // redirects) ourselves: its default client only trusts the system roots,
+    // and its base URI layer would also rewrite absolute URLs such as the
+    // Enterprise GraphQL endpoint.
+    let defaults = OctocrabBuilder::default();
+    let connector =
+        defaults.set_connect_timeout_service(https_connector(options.ca_cert.as_deref())?);
+    let client = hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
+        .build(connector);
+    let client = FollowRedirect::new(defaults.set_connector_retry_service(client));
+    let client = ApiRoot {
+        root: api_url.clone(),
+        inner: client,
+    };
+
+    let auth_header = match options.credentials {
+        Credentials::Token(ref token) => Some(
+            HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|_| {
+                McpGithubError::Other("token contains invalid characters".to_string())
+            })?,
+        ),
+        _ => None,
+    };
+    let auth_state = app_auth.map(AuthState::App).unwrap_or(AuthState::None);
+    let user_agent = vec![(USER_AGENT, HeaderValue::from_static("octocrab"))];
+
+    let github = OctocrabBuilder::new_empty()
+        .with_service(client)
+        .with_layer(&ExtraHeadersLayer::new(Arc::new(user_agent)))
+        .with_layer(&AuthHeaderLayer::new(auth_header, api_url, upload_url))
+        .with_auth(auth_state)
+        .build()
+        .unwrap_or_else(|never| match never {});
+    Ok(github)
+}
+
+/// Resolves relative request URIs against the API root, keeping any path
+/// in the root. Unlike octocrab's `BaseUriLayer`, requests that already name
+/// a host are sent unchanged.
+#[derive(Clone)]
+struct ApiRoot<S> {
+    root: Uri,
+    inner: S,
+}
+
+impl<S, B> Service<Request<B>> for ApiRoot<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        if req.uri().authority().is_none() {
+            let base = self.root.path().trim_end_matches('/');
+            let path = req.uri().path_and_query().map_or("/", |p| p.as_str());
+            let mut parts = self.root.clone().into_parts();
+            parts.path_and_query = format!("{}{}", base, path).parse().ok();
+            if let Ok(uri) = Uri::from_parts(parts) {
+                *req.uri_mut() = uri;
+            }
+        }
+        self.inner.call(req)
+    }
+}
+
+/// Switch an app-authenticated client to one of the app's installations.
+async fn installation_client(
+    app_client: Octocrab,
+    app: &AppCredentials,
+) -> Result<Octocrab, McpGithubError> {
+    let installation_id = match app.installation_id {
+        Some(id) => id,
+        None => {
+            let owner = app.owner.as_deref().ok_or_else(|| {
+                McpGithubError::MissingParam(
+                    "--installation-id or --owner is required for GitHub App authentication"
+                        .to_string(),
+                )
+            })?;
+            discover_installation(&app_client, owner).await?
+        }
+    };
+
+    let (github, _token) = app_client
+        .installation_and_token(InstallationId(installation_id))
+        .await?;
+    tracing::info!(
+        app_id = app.app_id,
+        installation_id,
+        "Authenticated as GitHub App installation"
+    );
+    Ok(github)
+}
+
+/// Find the app's installation on an organization or, failing that, a user.
+async fn discover_installation(github: &Octocrab, owner: &str) -> Result<u64, McpGithubError> {
+    for route in [
+        format!("/orgs/{}/installation", owner),
+        format!("/users/{}/installation", owner),
+    ] {
+        match github.get::<serde_json::Value, _, ()>(&route, None).await {
+            Ok(installation) => {
+                if let Some(id) = installation.get("id").and_then(|v| v.as_u64()) {
+                    return Ok(id);
+                }
+            }
+            Err(octocrab::Error::GitHub { ref source, .. })
+                if source.status_code == StatusCode::NOT_FOUND => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(McpGithubError::Other(format!(
+        "GitHub App is not installed on '{}'",
+        owner
+    )))
+}
+
+fn load_app_key(path: &Path) -> Result<jsonwebtoken::EncodingKey, McpGithubError> {
+    let pem = std::fs::read(path).map_err(|e| {
+        McpGithubError::Other(format!(
+            "Cannot read GitHub App private key {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    jsonwebtoken::EncodingKey::from_rsa_pem(&pem).map_err(|e| {
+        McpGithubError::Other(format!(
+            "Invalid GitHub App private key {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+fn parse_url(url: &str, field: &str) -> Result<Uri, McpGithubError> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|e| McpGithubError::Other(format!("Invalid {} '{}': {}", field, url, e)))?;
+    if uri.scheme().is_none() || uri.host().is_none() {
+        return Err(McpGithubError::Other(format!(
+            "Invalid {} '{}': expected an absolute http(s) URL",
+            field, url
+        )));
+    }
+    Ok(uri)
+}
+
+/// HTTPS connector trusting the system roots plus the certificates in `ca_cert`.
+fn https_connector(
+    ca_cert: Option<&Path>,
+) -> Result<HttpsConnector<HttpConnector>, McpGithubError> {
+    let mut roots = rustls::RootCertStore::empty();
+    let native = rustls_native_certs::load_native_certs();
+    for e in &native.errors {
+        tracing::warn!(error = %e, "Failed to load a system CA certificate");
+    }
+    roots.add_parsable_certificates(native.certs);
+
+    if let Some(ca_cert) = ca_cert {
+        for cert in load_ca_certs(ca_cert)? {
+            roots.add(cert).map_err(|e| {
+                McpGithubError::Other(format!(
+                    "Invalid CA certificate in {}: {}",
+                    ca_cert.display(),
+                    e
+                ))
+            })?;
+        }
+    }
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let tls = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| McpGithubError::Other(format!("TLS setup failed: {}", e)))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    Ok(HttpsConnectorBuilder::new()
+        .with_tls_config(tls)
+        .https_or_http()
+        .enable_http1()
+        .build())
+}
+
+fn load_ca_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, McpGithubError> {
+    let pem = std::fs::read(path).map_err(|e| {
+        McpGithubError::Other(format!(
+            "Cannot read CA certificate {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let certs = CertificateDer::pem_slice_iter(&pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            McpGithubError::Other(format!("Invalid PEM in {}: {:?}", path.display(), e))
+        })?;
+    if certs.is_empty() {
+        return Err(McpGithubError::Other(format!(
+            "No certificates found in {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// PKCS#1 PEM of an RSA key generated once per test run, so no private
+    /// key is checked in.
+    fn test_app_key() -> &'static str {
+        static KEY: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+        KEY.get_or_init(|| {
+            use rsa::pkcs1::{EncodeRsaPrivateKey, LineEnding};
+            let key = rsa::RsaPrivateKey::new(&mut rsa::rand_core::OsRng, 2048).unwrap();
+            key.to_pkcs1_pem(LineEnding::LF).unwrap().to_string()
+        })
+    }
+
+    /// A throwaway CA (as PEM) and a TLS config for 127.0.0.1 signed by it.
+    fn test_ca() -> (String, rustls::ServerConfig) {
+        let ca_key = rcgen::KeyPair::generate().unwrap();
+        let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "mcp-github test CA");
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let server_key = rcgen::KeyPair::generate().unwrap();
+        let server_cert = rcgen::CertificateParams::new(vec!["127.0.0.1".to_string()])
+            .unwrap()
+            .signed_by(&server_key, &ca, &ca_key)
+            .unwrap();
+        let key = rustls::pki_types::PrivatePkcs8KeyDer::from(server_key.serialize_der());
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let tls = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![server_cert.der().clone()], key.into())
+            .unwrap();
+        (ca.pem(), tls)
+    }
+
+    /// Answer every HTTPS request with `body`, recording request paths.
+    /// Returns the server's base URL.
+    async fn tls_server(
+        tls: rustls::ServerConfig,
+        body: &'static str,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://{}", listener.local_addr().unwrap());
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tls));
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let seen = paths.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (acceptor, seen) = (acceptor.clone(), seen.clone());
+                tokio::spawn(async move {
+                    // Clients that don't trust the CA fail the handshake
+                    let Ok(mut stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let request = String::from_utf8_lossy(&request);
+                    if let Some(path) = request.split_whitespace().nth(1) {
+                        seen.lock().unwrap().push(path.to_string());
+                    }
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                         content-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+        (url, paths)
+    }
+
+    fn write_temp(name: &str, contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("mcp-github-{}-{}.pem", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    async fn mock_enterprise(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/o/r"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "r"
+            })))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/o/r/issues/1"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 1, "node_id": "I_1", "url": "https://ghe.test/api/v3/repos/o/r/issues/1",
+                "repository_url": "https://ghe.test/api/v3/repos/o/r",
+                "labels_url": "https://ghe.test/l", "comments_url": "https://ghe.test/c",
+                "events_url": "https://ghe.test/e", "html_url": "https://ghe.test/o/r/issues/1",
+                "number": 1, "state": "open", "title": "t", "body": null,
+                "user": {
+                    "login": "u", "id": 1, "node_id": "U_1", "avatar_url": "https://ghe.test/a",
+                    "gravatar_id": "", "url": "https://ghe.test/u", "html_url": "https://ghe.test/u",
+                    "followers_url": "https://ghe.test/f", "following_url": "https://ghe.test/f",
+                    "gists_url": "https://ghe.test/g", "starred_url": "https://ghe.test/s",
+                    "subscriptions_url": "https://ghe.test/s",
+                    "organizations_url": "https://ghe.test/o", "repos_url": "https://ghe.test/r",
+                    "events_url": "https://ghe.test/e",
+                    "received_events_url": "https://ghe.test/r", "type": "User",
+                    "site_admin": false
+                },
+                "labels": [], "assignees": [], "author_association": "NONE",
+                "locked": false, "comments": 0,
+                "created_at": "2024-01-01T00:00:00Z", "updated_at": "2024-01-01T00:00:00Z"
+            })))
+            .mount(server)
+            .await;
+    }
+
+    async fn assert_reaches_enterprise(github: &Octocrab) {
+        let repo: serde_json::Value = github.get("/repos/o/r", None::<&()>).await.unwrap();
+        assert_eq!(repo["name"], "r");
+        let issue = github.issues("o", "r").get(1).await.unwrap();
+        assert_eq!(issue.number, 1);
+    }
+
+    #[test]
+    fn test_upload_url_defaults() {
+        assert_eq!(ClientOptions::default().upload_url(), DEFAULT_UPLOAD_URL);
+
+        let ghes = ClientOptions {
+            api_url: Some("https://ghe.example.com/api/v3/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ghes.upload_url(), "https://ghe.example.com/api/uploads");
+
+        let explicit = ClientOptions {
+            api_url: Some("https://ghe.example.com/api/v3".to_string()),
+            upload_url: Some("https://uploads.ghe.example.com".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(explicit.upload_url(), "https://uploads.ghe.example.com");
+    }
+
+    #[test]
+    fn test_cache_scope_separates_hosts_and_credentials() {
+        let token = |t: &str, api: Option<&str>| ClientOptions {
+            credentials: Credentials::Token(t.to_string()),
+            api_url: api.map(String::from),
+            ..Default::default()
+        };
+        let scope = token("secret", None).cache_scope();
+        assert!(scope.starts_with("https://api.github.com "), "{}", scope);
+        assert!(!scope.contains("secret"), "{}", scope);
+        assert_eq!(scope, token("secret", None).cache_scope());
+        assert_ne!(scope, token("other", None).cache_scope());
+        assert_ne!(
+            scope,
+            token("secret", Some("https://ghe.example.com/api/v3")).cache_scope()
+        );
+        assert_ne!(scope, ClientOptions::default().cache_scope());
+    }
+
+    #[test]
+    fn test_graphql_url() {
+        assert_eq!(
+            ClientOptions::default().graphql_url(),
+            "https://api.github.com/graphql"
+        );
+        let ghes = ClientOptions {
+            api_url: Some("https://ghe.example.com/api/v3/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ghes.graphql_url(), "https://ghe.example.com/api/graphql");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_relative_api_url() {
+        let options = ClientOptions {
+            api_url: Some("ghe.example.com/api/v3".to_string()),
+            ..Default::default()
+        };
+        assert!(build(&options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_routes_use_enterprise_base() {
+        let server = MockServer::start().await;
+        mock_enterprise(&server).await;
+
+        let github = build(&ClientOptions {
+            credentials: Credentials::Token("secret".to_string()),
+            api_url: Some(format!("{}/api/v3", server.uri())),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_reaches_enterprise(&github).await;
+    }
+
+    #[tokio::test]
+    async fn test_custom_ca_is_trusted_for_enterprise_tls() {
+        let (ca_pem, tls) = test_ca();
+        let (url, paths) = tls_server(tls, r#"{"name": "r"}"#).await;
+        let ca = write_temp("ca", &ca_pem);
+
+        let options = ClientOptions {
+            credentials: Credentials::Token("secret".to_string()),
+            api_url: Some(format!("{}/api/v3", url)),
+            ca_cert: Some(ca.clone()),
+            ..Default::default()
+        };
+        let github = build(&options).await.unwrap();
+        let repo: serde_json::Value = github.get("/repos/o/r", None::<&()>).await.unwrap();
+        assert_eq!(repo["name"], "r");
+        assert_eq!(paths.lock().unwrap().as_slice(), ["/api/v3/repos/o/r"]);
+
+        // The server's certificate is rejected without the extra CA
+        let untrusted = build(&ClientOptions {
+            ca_cert: None,
+            ..options
+        })
+        .await
+        .unwrap();
+        let result = untrusted
+            .get::<serde_json::Value, _, ()>("/repos/o/r", None)
+            .await;
+        assert!(result.is_err());
+        assert_eq!(paths.lock().unwrap().len(), 1);
+
+        let _ = std::fs::remove_file(ca);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_ca_file() {
+        let bogus = write_temp("bogus", "not a certificate");
+        let result = build(&ClientOptions {
+            ca_cert: Some(bogus.clone()),
+            ..Default::default()
+        })
+        .await;
+        assert!(result.is_err());
+        let _ = std::fs::remove_file(bogus);
+
+        let missing = build(&ClientOptions {
+            ca_cert: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        })
+        .await;
+        assert!(missing.is_err());
+    }
+
+    async fn mock_access_token(server: &MockServer, installation: u64, token: &str, ttl_secs: i64) {
+        let expires_at = chrono::Utc::now() + chrono::Duration::seconds(ttl_secs);
+        Mock::given(method("POST"))
+            .and(path(format!(
+                "/app/installations/{}/access_tokens",
+                installation
+            )))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "token": token,
+                "expires_at": expires_at.to_rfc3339(),
+                "permissions": {},
+            })))
+            .mount(server)
+            .await;
+    }
+
+    async fn mock_repo_for_token(server: &MockServer, token: &str) {
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r"))
+            .and(header(
+                "authorization",
+                format!("Bearer {}", token).as_str(),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "r"
+            })))
+            .mount(server)
+            .await;
+    }
+
+    fn app_options(server: &MockServer, key: &Path, installation_id: Option<u64>) -> ClientOptions {
+        ClientOptions {
+            credentials: Credentials::App(AppCredentials {
+                app_id: 1,
+                private_key: key.to_path_buf(),
+                installation_id,
+                owner: Some("acme".to_string()),
+            }),
+            api_url: Some(server.uri()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_app_uses_installation_token() {
+        let server = MockServer::start().await;
+        mock_access_token(&server, 42, "ghs_long", 3600).await;
+        mock_repo_for_token(&server, "ghs_long").await;
+        let key = write_temp("app-key", test_app_key());
+
+        let github = build(&app_options(&server, &key, Some(42))).await.unwrap();
+        for _ in 0..2 {
+            let repo: serde_json::Value = github.get("/repos/o/r", None::<&()>).await.unwrap();
+            assert_eq!(repo["name"], "r");
+        }
+
+        // A token that is still valid is reused rather than minted per request
+        let minted = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.url.path().ends_with("/access_tokens"))
+            .count();
+        assert_eq!(minted, 1);
+        let _ = std::fs::remove_file(key);
+    }
+
+    #[tokio::test]
+    async fn test_app_discovers_installation_and_renews_expiring_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/orgs/acme/installation"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "message": "Not Found"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/users/acme/installation"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 7
+            })))
+            .mount(&server)
+            .await;
+        // Expires inside octocrab's renewal window, so every use re-mints
+        mock_access_token(&server, 7, "ghs_short", 10).await;
+        mock_repo_for_token(&server, "ghs_short").await;
+        let key = write_temp("app-key-discover", test_app_key());
+
+        let github = build(&app_options(&server, &key, None)).await.unwrap();
+        let _: serde_json::Value = github.get("/repos/o/r", None::<&()>).await.unwrap();
+
+        let minted = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.url.path().ends_with("/access_tokens"))
+            .count();
+        assert_eq!(minted, 2);
+        let _ = std::fs::remove_file(key);
+    }
+
+    #[tokio::test]
+    async fn test_app_requires_installation_or_owner() {
+        let server = MockServer::start().await;
+        let key = write_temp("app-key-missing", test_app_key());
+        let mut options = app_options(&server, &key, None);
+        if let Credentials::App(ref mut app) = options.credentials {
+            app.owner = None;
+        }
+        assert!(matches!(
+            build(&options).await,
+            Err(McpGithubError::MissingParam(_))
+        ));
+        let _ = std::fs::remove_file(key);
+    }
+
+    #[tokio::test]
+    async fn test_app_rejects_invalid_key() {
+        let server = MockServer::start().await;
+        let key = write_temp("app-key-bogus", "not a key");
+        assert!(build(&app_options(&server, &key, Some(1))).await.is_err());
+        let _ = std::fs::remove_file(key);
+    }
+}
@@ -0,0 +1,287 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::policy::RepoPolicy;
+use crate::toolsets::{self, Toolset};
+
+/// Settings read from a TOML file. Every field is optional; command-line
+/// flags override whatever is set here.
+///
+/// ```toml
+/// owner = "myorg"
+/// max_results = 50
+/// toolsets = ["repos", "pulls"]
+/// token_env = "MYORG_GITHUB_TOKEN"
+///
+/// [cache]
+/// ttl = 60
+///
+/// [transport]
+/// mode = "http"
+/// bind = "127.0.0.1:8080"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Environment variable holding the token. Tokens themselves are not
+    /// read from the file so it can be shared or checked in.
+    pub token_env: Option<String>,
+    pub owner: Option<String>,
+    pub max_results: Option<u32>,
+    pub allow_writes: Option<bool>,
+    pub error_results: Option<bool>,
+    pub resource_repo: Option<String>,
+    pub api_url: Option<String>,
+    pub upload_url: Option<String>,
+    pub ca_cert: Option<PathBuf>,
+    pub toolsets: Option<Vec<Toolset>>,
+    pub disable_tools: Option<Vec<String>>,
+    pub allow_repos: Option<Vec<String>>,
+    pub deny_repos: Option<Vec<String>>,
+    #[serde(default)]
+    pub app: AppConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub transport: TransportConfig,
+}
+
+/// `[app]`: authenticate as a GitHub App.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfig {
+    pub id: Option<u64>,
+    pub private_key: Option<PathBuf>,
+    pub installation_id: Option<u64>,
+}
+
+/// `[cache]`: response cache settings.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    pub ttl: Option<u64>,
+    pub dir: Option<PathBuf>,
+}
+
+/// `[transport]`: how MCP is served.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransportConfig {
+    pub mode: Option<TransportMode>,
+    pub bind: Option<SocketAddr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportMode {
+    Stdio,
+    Http,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid config file {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+
+    #[error("invalid config file {}: `{key}` {message}", path.display())]
+    Invalid {
+        path: PathBuf,
+        key: &'static str,
+        message: String,
+    },
+}
+
+/// Split an `OWNER/REPO` name, rejecting empty halves and extra slashes.
+pub fn split_full_name(full_name: &str) -> Result<(&str, &str), String> {
+    match full_name.split_once('/') {
+        Some((o, r)) if !o.is_empty() && !r.is_empty() && !r.contains('/') => Ok((o, r)),
+        _ => Err(format!("must be OWNER/REPO, got '{}'", full_name)),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/mcp-github/config.toml`, with `XDG_CONFIG_HOME`
+/// defaulting to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("mcp-github").join("config.toml"))
+}
+
+impl Config {
+    /// Load and validate the config file at `path`.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&text, path)
+    }
+
+    /// Parse and validate config text; `path` is only used in errors.
+    pub fn parse(text: &str, path: &Path) -> Result<Self, ConfigError> {
+        let config: Config = toml::from_str(text).map_err(|e| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        config
+            .validate()
+            .map_err(|(key, message)| ConfigError::Invalid {
+                path: path.to_path_buf(),
+                key,
+                message,
+            })?;
+        Ok(config)
+    }
+
+    /// Checks serde can't express, reported as the offending key.
+    fn validate(&self) -> Result<(), (&'static str, String)> {
+        if self.max_results == Some(0) {
+            return Err(("max_results", "must be at least 1".to_string()));
+        }
+        if let Some(ref repo) = self.resource_repo {
+            split_full_name(repo).map_err(|e| ("resource_repo", e))?;
+        }
+        for (key, value) in [
+            ("token_env", &self.token_env),
+            ("owner", &self.owner),
+            ("api_url", &self.api_url),
+            ("upload_url", &self.upload_url),
+        ] {
+            if value.as_deref() == Some("") {
+                return Err((key, "must not be empty".to_string()));
+            }
+        }
+        if let Some(ref tools) = self.disable_tools {
+            toolsets::check_tool_names(tools).map_err(|e| ("disable_tools", e))?;
+        }
+        for (key, patterns) in [
+            ("allow_repos", &self.allow_repos),
+            ("deny_repos", &self.deny_repos),
+        ] {
+            if let Some(patterns) = patterns {
+                RepoPolicy::new(patterns, &[]).map_err(|e| (key, e))?;
+            }
+        }
+        if self.app.private_key.is_some() && self.app.id.is_none() {
+            return Err(("app.private_key", "requires app.id".to_string()));
+        }
+        if self.app.installation_id.is_some() && self.app.id.is_none() {
+            return Err(("app.installation_id", "requires app.id".to_string()));
+        }
+        if self.app.id.is_some() && self.app.private_key.is_none() {
+            return Err(("app.id", "requires app.private_key".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Config, ConfigError> {
+        Config::parse(text, Path::new("config.toml"))
+    }
+
+    #[test]
+    fn test_parse_full_config() {
+        let config = parse(
+            r#"
+            owner = "myorg"
+            max_results = 50
+            allow_writes = true
+            error_results = true
+            token_env = "MYORG_TOKEN"
+            resource_repo = "myorg/api"
+            toolsets = ["repos", "pulls"]
+            disable_tools = ["get_blame"]
+            allow_repos = ["myorg/*"]
+            deny_repos = ["myorg/secrets"]
+            api_url = "https://ghe.example.com/api/v3"
+
+            [app]
+            id = 42
+            private_key = "/etc/mcp-github/app.pem"
+
+            [cache]
+            ttl = 60
+            dir = "/var/cache/mcp-github"
+
+            [transport]
+            mode = "http"
+            bind = "0.0.0.0:9000"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.owner.as_deref(), Some("myorg"));
+        assert_eq!(config.max_results, Some(50));
+        assert_eq!(config.toolsets, Some(vec![Toolset::Repos, Toolset::Pulls]));
+        assert_eq!(config.app.id, Some(42));
+        assert_eq!(config.cache.ttl, Some(60));
+        assert_eq!(config.transport.mode, Some(TransportMode::Http));
+        assert_eq!(config.transport.bind, Some("0.0.0.0:9000".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_empty_config_is_default() {
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_errors_name_the_offending_key() {
+        let err = parse("max_results = \"many\"").unwrap_err().to_string();
+        assert!(err.contains("max_results"), "{}", err);
+
+        let err = parse("maxresults = 5").unwrap_err().to_string();
+        assert!(err.contains("maxresults"), "{}", err);
+
+        let err = parse("[transport]\nmode = \"tcp\"")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("mode"), "{}", err);
+
+        let err = parse("max_results = 0").unwrap_err().to_string();
+        assert!(err.contains("`max_results` must be at least 1"), "{}", err);
+
+        let err = parse("resource_repo = \"api\"").unwrap_err().to_string();
+        assert!(err.contains("`resource_repo`"), "{}", err);
+        assert_eq!(split_full_name("o/r"), Ok(("o", "r")));
+        for bad in ["o/", "/r", "o/r/x"] {
+            assert!(split_full_name(bad).is_err(), "{}", bad);
+        }
+
+        let err = parse("toolsets = [\"wiki\"]").unwrap_err().to_string();
+        assert!(err.contains("toolsets"), "{}", err);
+
+        let err = parse("disable_tools = [\"get_trees\"]")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("`disable_tools` unknown tool"), "{}", err);
+
+        let err = parse("deny_repos = [\"myorg\"]").unwrap_err().to_string();
+        assert!(
+            err.contains("`deny_repos` 'myorg' must look like OWNER/REPO"),
+            "{}",
+            err
+        );
+
+        let err = parse("[app]\nid = 1").unwrap_err().to_string();
+        assert!(err.contains("`app.id` requires app.private_key"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let err = Config::load(Path::new("/nonexistent/mcp-github.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::Read { .. }));
+    }
+}
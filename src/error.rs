@@ -1,3 +1,4 @@
+use http::{HeaderMap, StatusCode};
 use rmcp::model::ErrorData;
 
 #[derive(Debug, thiserror::Error)]
@@ -11,25 +12,420 @@ pub enum McpGithubError {
     #[error("Repository not found: {0}")]
     RepoNotFound(String),
 
-    #[error("Authentication required")]
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Authentication required: the GitHub token is missing, invalid, or expired")]
     Unauthenticated,
 
+    #[error("The GitHub token lacks the required scope; it needs one of: {}", needed.join(", "))]
+    InsufficientScope { needed: Vec<String> },
+
+    #[error("GitHub rejected the request: {message}{}", field_errors(errors))]
+    Validation {
+        message: String,
+        errors: Vec<String>,
+    },
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("GitHub API rate limit exhausted; resets at {reset_at}")]
+    RateLimited { reset_at: chrono::DateTime<chrono::Utc> },
+
+    #[error("GitHub secondary rate limit hit; {}", retry_hint(*retry_after))]
+    SecondaryRateLimited { retry_after: Option<u64> },
+
+    #[error("GraphQL error: {0}")]
+    GraphQL(String),
+
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+
     #[error("{0}")]
     Other(String),
 }
 
+fn retry_hint(retry_after: Option<u64>) -> String {
+    match retry_after {
+        Some(secs) => format!("retry after {} seconds", secs),
+        None => "slow down and retry later".to_string(),
+    }
+}
+
+fn field_errors(errors: &[String]) -> String {
+    if errors.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", errors.join("; "))
+    }
+}
+
 impl McpGithubError {
-    pub fn to_mcp_error(&self) -> ErrorData {
+    /// Map a failed GitHub REST response to the variant an LLM can act on.
+    /// `target` names what was requested (e.g. `issue o/r#5`) for 404s;
+    /// `headers` are used for OAuth scope hints when available. Rate limits
+    /// are classified separately, before this is called.
+    pub fn from_github(err: octocrab::Error, headers: Option<&HeaderMap>, target: &str) -> Self {
+        let octocrab::Error::GitHub { ref source, .. } = err else {
+            return McpGithubError::GitHub(err);
+        };
+        match source.status_code {
+            StatusCode::UNAUTHORIZED => McpGithubError::Unauthenticated,
+            StatusCode::NOT_FOUND if target.starts_with("repository ") => {
+                McpGithubError::RepoNotFound(format!(
+                    "{} (or the token cannot see it)",
+                    &target["repository ".len()..]
+                ))
+            }
+            StatusCode::NOT_FOUND => {
+                McpGithubError::NotFound(format!("{} (or the token cannot see it)", target))
+            }
+            StatusCode::GONE => {
+                McpGithubError::NotFound(format!("{} is no longer available", target))
+            }
+            StatusCode::FORBIDDEN => match headers.and_then(missing_scopes) {
+                Some(needed) => McpGithubError::InsufficientScope { needed },
+                None => McpGithubError::Forbidden(format!("{}: {}", target, source.message)),
+            },
+            StatusCode::UNPROCESSABLE_ENTITY => McpGithubError::Validation {
+                message: source.message.clone(),
+                errors: source
+                    .errors
+                    .iter()
+                    .flatten()
+                    .map(describe_field_error)
+                    .collect(),
+            },
+            _ => McpGithubError::GitHub(err),
+        }
+    }
+
+    /// Stable identifier for the kind of failure, e.g. `not_found`.
+    pub fn code(&self) -> &'static str {
         match self {
-            McpGithubError::MissingParam(_) | McpGithubError::RepoNotFound(_) => {
-                ErrorData::invalid_params(self.to_string(), None)
+            McpGithubError::GitHub(_) => "github_error",
+            McpGithubError::MissingParam(_) => "invalid_params",
+            McpGithubError::RepoNotFound(_) | McpGithubError::NotFound(_) => "not_found",
+            McpGithubError::Unauthenticated => "unauthenticated",
+            McpGithubError::Forbidden(_) => "forbidden",
+            McpGithubError::InsufficientScope { .. } => "insufficient_scope",
+            McpGithubError::Validation { .. } => "validation_failed",
+            McpGithubError::RateLimited { .. } => "rate_limited",
+            McpGithubError::SecondaryRateLimited { .. } => "secondary_rate_limited",
+            McpGithubError::GraphQL(_) => "graphql_error",
+            McpGithubError::LimitExceeded(_) => "limit_exceeded",
+            McpGithubError::Other(_) => "error",
+        }
+    }
+
+    /// What the caller can do about it.
+    fn hint(&self) -> Option<&'static str> {
+        Some(match self {
+            McpGithubError::GitHub(_) => "GitHub failed to answer; retry later",
+            McpGithubError::MissingParam(_) => "Fix the tool arguments and call again",
+            McpGithubError::RepoNotFound(_) | McpGithubError::NotFound(_) => {
+                "Check the owner, repository, and number (list or search tools can find them); \
+                 private resources the token cannot see also look missing"
+            }
+            McpGithubError::Unauthenticated => {
+                "The server's GitHub token is missing or invalid; ask the user to configure one"
+            }
+            McpGithubError::Forbidden(_) => {
+                "This repository or action is off limits; do not retry it, pick another target"
+            }
+            McpGithubError::InsufficientScope { .. } => {
+                "Ask the user for a token with one of the needed scopes"
+            }
+            McpGithubError::Validation { .. } => "Correct the listed fields and call again",
+            McpGithubError::RateLimited { .. } => {
+                "Wait until reset_at before calling GitHub again; get_rate_limit shows the quota"
+            }
+            McpGithubError::SecondaryRateLimited { .. } => "Make fewer, slower calls, then retry",
+            McpGithubError::GraphQL(_) => "Fix the query using the error messages",
+            McpGithubError::LimitExceeded(_) => {
+                "Ask for less: fewer fields, smaller first/last values, or smaller pages"
+            }
+            McpGithubError::Other(_) => return None,
+        })
+    }
+
+    /// GitHub documentation for the failure: the link GitHub sent, or a
+    /// general page for the error kind.
+    fn docs_url(&self) -> Option<String> {
+        let page = match self {
+            McpGithubError::GitHub(octocrab::Error::GitHub { source, .. }) => {
+                return source.documentation_url.clone();
             }
             McpGithubError::Unauthenticated => {
-                ErrorData::invalid_params(self.to_string(), None)
+                "https://docs.github.com/rest/authentication/authenticating-to-the-rest-api"
+            }
+            McpGithubError::InsufficientScope { .. } => {
+                "https://docs.github.com/apps/oauth-apps/building-oauth-apps/scopes-for-oauth-apps"
+            }
+            McpGithubError::RateLimited { .. } | McpGithubError::SecondaryRateLimited { .. } => {
+                "https://docs.github.com/rest/using-the-rest-api/rate-limits-for-the-rest-api"
+            }
+            McpGithubError::RepoNotFound(_)
+            | McpGithubError::NotFound(_)
+            | McpGithubError::Validation { .. } => {
+                "https://docs.github.com/rest/using-the-rest-api/troubleshooting-the-rest-api"
+            }
+            _ => return None,
+        };
+        Some(page.to_string())
+    }
+
+    /// Structured description: `code`, `message`, `hint`, and `docs_url`,
+    /// plus fields specific to the error kind (`reset_at`, `needed_scopes`,
+    /// ...). Sent as error data, and as the body of an `isError` tool result
+    /// when the server reports failures as results.
+    pub fn body(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "hint": self.hint(),
+            "docs_url": self.docs_url(),
+        });
+        match self {
+            McpGithubError::RateLimited { reset_at } => {
+                body["reset_at"] = reset_at.to_rfc3339().into();
+            }
+            McpGithubError::SecondaryRateLimited { retry_after } => {
+                body["retry_after_secs"] = serde_json::json!(retry_after);
+            }
+            McpGithubError::InsufficientScope { needed } => {
+                body["needed_scopes"] = serde_json::json!(needed);
+            }
+            McpGithubError::Validation { errors, .. } => {
+                body["errors"] = serde_json::json!(errors);
+            }
+            _ => {}
+        }
+        body
+    }
+
+    pub fn to_mcp_error(&self) -> ErrorData {
+        let data = Some(self.body());
+        match self {
+            McpGithubError::MissingParam(_)
+            | McpGithubError::RepoNotFound(_)
+            | McpGithubError::NotFound(_)
+            | McpGithubError::Forbidden(_)
+            | McpGithubError::InsufficientScope { .. }
+            | McpGithubError::Validation { .. }
+            | McpGithubError::Unauthenticated
+            | McpGithubError::GraphQL(_)
+            | McpGithubError::LimitExceeded(_) => ErrorData::invalid_params(self.to_string(), data),
+            McpGithubError::RateLimited { .. } | McpGithubError::SecondaryRateLimited { .. } => {
+                ErrorData::invalid_request(self.to_string(), data)
             }
             McpGithubError::GitHub(_) | McpGithubError::Other(_) => {
-                ErrorData::internal_error(self.to_string(), None)
+                ErrorData::internal_error(self.to_string(), data)
             }
         }
     }
 }
+
+/// Describe what an API route addresses, for error messages:
+/// `/repos/o/r/issues/5/comments` becomes `issue o/r#5`. A 404 on a list
+/// directly under a repository means the repository itself is missing.
+pub fn route_target(route: &str) -> String {
+    let path = route.split('?').next().unwrap_or(route);
+    let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let number = |n: &str| n.parse::<u64>().is_ok();
+    match parts.as_slice() {
+        ["repos", o, r] => format!("repository {}/{}", o, r),
+        ["repos", o, r, "issues" | "pulls" | "branches" | "tags" | "releases" | "commits"] => {
+            format!("repository {}/{}", o, r)
+        }
+        ["repos", o, r, "issues", n, ..] if number(n) => format!("issue {}/{}#{}", o, r, n),
+        ["repos", o, r, "pulls", n, ..] if number(n) => {
+            format!("pull request {}/{}#{}", o, r, n)
+        }
+        ["repos", o, r, "commits", sha, ..] => format!("commit {} in {}/{}", sha, o, r),
+        ["repos", o, r, "actions", "runs", id, ..] => {
+            format!("workflow run {} in {}/{}", id, o, r)
+        }
+        ["repos", o, r, "actions", "jobs", id, ..] => format!("job {} in {}/{}", id, o, r),
+        ["repos", o, r, "contents"] => format!("path '/' in {}/{}", o, r),
+        ["repos", o, r, "contents", path @ ..] => {
+            format!("path '{}' in {}/{}", path.join("/"), o, r)
+        }
+        ["repos", o, r, "readme", ..] => format!("README in {}/{}", o, r),
+        ["repos", o, r, "git", "trees", tree] => format!("tree {} in {}/{}", tree, o, r),
+        ["repos", o, r, "compare", spec] => format!("comparison {} in {}/{}", spec, o, r),
+        ["orgs", o, ..] => format!("organization {}", o),
+        ["users", u, ..] => format!("user {}", u),
+        _ => path.to_string(),
+    }
+}
+
+/// Scopes the endpoint accepts when the token has none of them, from
+/// `x-accepted-oauth-scopes` and `x-oauth-scopes`. Only classic OAuth and
+/// personal access tokens send these headers.
+fn missing_scopes(headers: &HeaderMap) -> Option<Vec<String>> {
+    let scopes = |name| -> Option<Vec<String>> {
+        let value = headers.get(name)?.to_str().ok()?;
+        Some(
+            value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+        )
+    };
+    let accepted = scopes("x-accepted-oauth-scopes")?;
+    let granted = scopes("x-oauth-scopes")?;
+    if accepted.is_empty() || accepted.iter().any(|s| granted.contains(s)) {
+        return None;
+    }
+    Some(accepted)
+}
+
+/// One entry of a 422 response's `errors` array as a sentence, e.g.
+/// `title is required`.
+fn describe_field_error(error: &serde_json::Value) -> String {
+    if let Some(text) = error.as_str() {
+        return text.to_string();
+    }
+    let get = |key| error.get(key).and_then(|v| v.as_str());
+    if let Some(message) = get("message") {
+        return match get("field") {
+            Some(field) if !message.contains(field) => format!("{}: {}", field, message),
+            _ => message.to_string(),
+        };
+    }
+    let field = get("field").unwrap_or("request");
+    match get("code") {
+        Some("missing_field") => format!("{} is required", field),
+        Some("invalid") => format!("{} is invalid", field),
+        Some("already_exists") => format!("{} already exists", field),
+        Some("missing") => format!("{} does not exist", field),
+        Some("unprocessable") => format!("{} could not be processed", field),
+        Some(code) => format!("{} {}", field, code),
+        None => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn github_error(status: u16, body: serde_json::Value) -> octocrab::Error {
+        use http_body_util::BodyExt;
+        let body = http_body_util::Full::new(bytes::Bytes::from(body.to_string()))
+            .map_err(|never| match never {})
+            .boxed();
+        let response = http::Response::builder().status(status).body(body).unwrap();
+        octocrab::map_github_error(response).await.unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn test_not_found_names_target() {
+        let not_found = || github_error(404, serde_json::json!({"message": "Not Found"}));
+        let err = McpGithubError::from_github(not_found().await, None, "issue o/r#5");
+        assert!(matches!(err, McpGithubError::NotFound(_)));
+        assert!(err.to_string().contains("issue o/r#5"), "{}", err);
+
+        let err = McpGithubError::from_github(not_found().await, None, "repository o/r");
+        assert!(matches!(err, McpGithubError::RepoNotFound(ref t) if t.starts_with("o/r ")));
+
+        let gone = github_error(410, serde_json::json!({"message": "Gone"})).await;
+        let err = McpGithubError::from_github(gone, None, "job 8 in o/r");
+        assert!(matches!(err, McpGithubError::NotFound(_)));
+        assert!(err.to_string().contains("no longer available"), "{}", err);
+    }
+
+    #[test]
+    fn test_route_target() {
+        assert_eq!(route_target("/repos/o/r"), "repository o/r");
+        assert_eq!(
+            route_target("/repos/o/r/branches?per_page=5"),
+            "repository o/r"
+        );
+        assert_eq!(route_target("/repos/o/r/issues/5/comments"), "issue o/r#5");
+        assert_eq!(
+            route_target("/repos/o/r/pulls/7/files"),
+            "pull request o/r#7"
+        );
+        assert_eq!(
+            route_target("/repos/o/r/contents/src/lib.rs?ref=main"),
+            "path 'src/lib.rs' in o/r"
+        );
+        assert_eq!(
+            route_target("/repos/o/r/contents?ref=missing"),
+            "path '/' in o/r"
+        );
+        assert_eq!(route_target("/repos/o/r/readme"), "README in o/r");
+        assert_eq!(
+            route_target("/repos/o/r/issues?state=open"),
+            "repository o/r"
+        );
+        assert_eq!(
+            route_target("/repos/o/r/collaborators"),
+            "/repos/o/r/collaborators"
+        );
+        assert_eq!(
+            route_target("/repos/o/r/commits/abc/check-runs"),
+            "commit abc in o/r"
+        );
+        assert_eq!(route_target("/rate_limit"), "/rate_limit");
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_and_forbidden() {
+        let unauthorized =
+            github_error(401, serde_json::json!({"message": "Bad credentials"})).await;
+        assert!(matches!(
+            McpGithubError::from_github(unauthorized, None, "repository o/r"),
+            McpGithubError::Unauthenticated
+        ));
+
+        let forbidden = || {
+            github_error(
+                403,
+                serde_json::json!({"message": "Resource not accessible by integration"}),
+            )
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("x-accepted-oauth-scopes", "repo".parse().unwrap());
+        headers.insert("x-oauth-scopes", "read:org, public_repo".parse().unwrap());
+        match McpGithubError::from_github(forbidden().await, Some(&headers), "issue o/r#5") {
+            McpGithubError::InsufficientScope { needed } => assert_eq!(needed, vec!["repo"]),
+            other => panic!("unexpected: {:?}", other),
+        }
+
+        headers.insert("x-oauth-scopes", "repo".parse().unwrap());
+        let err = McpGithubError::from_github(forbidden().await, Some(&headers), "issue o/r#5");
+        assert!(matches!(err, McpGithubError::Forbidden(_)));
+        assert!(err.to_string().contains("not accessible"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_validation_errors_list_fields() {
+        let err = github_error(
+            422,
+            serde_json::json!({
+                "message": "Validation Failed",
+                "errors": [
+                    {"resource": "Issue", "field": "title", "code": "missing_field"},
+                    {"resource": "Issue", "field": "milestone", "code": "invalid"},
+                    {"resource": "Label", "code": "custom", "field": "name",
+                     "message": "name is too long"},
+                ]
+            }),
+        )
+        .await;
+        let err = McpGithubError::from_github(err, None, "repository o/r");
+        assert_eq!(
+            err.to_string(),
+            "GitHub rejected the request: Validation Failed \
+             (title is required; milestone is invalid; name is too long)"
+        );
+        assert_eq!(
+            err.to_mcp_error().code,
+            rmcp::model::ErrorCode::INVALID_PARAMS
+        );
+    }
+}
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::error::McpGithubError;
+
+/// Longest query text accepted by `graphql_query`.
+pub const MAX_QUERY_BYTES: usize = 16 * 1024;
+
+/// Ceiling on the estimated number of nodes a query may request. GitHub
+/// allows 500,000; we stay well below so one call cannot flood the context.
+pub const MAX_NODES: u64 = 50_000;
+
+/// Largest serialized response `graphql_query` returns.
+pub const MAX_RESPONSE_BYTES: usize = 512 * 1024;
+
+/// Page size assumed for `first`/`last` arguments given as variables that
+/// were not supplied. GitHub caps connections at 100 items per page.
+const DEFAULT_PAGE_SIZE: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Int(u64),
+    Punct(char),
+    /// `...`, before a fragment name or an inline fragment.
+    Spread,
+}
+
+/// Split a GraphQL document into the tokens we care about, dropping
+/// comments, strings, commas, and whitespace. Fails on strings and comments
+/// that are not terminated, since GitHub would read the rest of the
+/// document differently than we do.
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '#' {
+            // A comment ends at a line terminator: `\n`, `\r`, or `\r\n`.
+            while i < chars.len() && chars[i] != '\n' && chars[i] != '\r' {
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err("comment is not terminated by a line break".to_string());
+            }
+        } else if chars[i..].starts_with(&['"', '"', '"']) {
+            // Block strings only escape `\"""`; any other backslash is literal.
+            i += 3;
+            loop {
+                if i >= chars.len() {
+                    return Err("block string is not terminated".to_string());
+                } else if chars[i..].starts_with(&['\\', '"', '"', '"']) {
+                    i += 4;
+                } else if chars[i..].starts_with(&['"', '"', '"']) {
+                    i += 3;
+                    break;
+                } else {
+                    i += 1;
+                }
+            }
+        } else if c == '"' {
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None | Some('\n') | Some('\r') => {
+                        return Err("string is not terminated".to_string())
+                    }
+                    Some('\\') => match chars.get(i + 1) {
+                        None | Some('\n') | Some('\r') => {
+                            return Err("string is not terminated".to_string())
+                        }
+                        Some(_) => i += 2,
+                    },
+                    Some('"') => {
+                        i += 1;
+                        break;
+                    }
+                    Some(_) => i += 1,
+                }
+            }
+        } else if chars[i..].starts_with(&['.', '.', '.']) {
+            tokens.push(Token::Spread);
+            i += 3;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit() || c == '-' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || ".+-".contains(chars[i]))
+            {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            if let Ok(n) = text.parse() {
+                tokens.push(Token::Int(n));
+            }
+        } else {
+            if "{}():$".contains(c) {
+                tokens.push(Token::Punct(c));
+            }
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// The operations defined in a document, in order. Shorthand `{ ... }`
+/// counts as a query; fragments are skipped.
+fn operations(tokens: &[Token]) -> Vec<Operation> {
+    let mut ops = Vec::new();
+    let (mut braces, mut parens) = (0usize, 0usize);
+    let mut named = false;
+    for token in tokens {
+        match token {
+            Token::Punct('(') => parens += 1,
+            Token::Punct(')') => parens = parens.saturating_sub(1),
+            // Object values in arguments use braces too; only selection
+            // sets (outside parentheses) nest.
+            Token::Punct('{') if parens == 0 => {
+                if braces == 0 && !named {
+                    ops.push(Operation::Query);
+                }
+                named = false;
+                braces += 1;
+            }
+            Token::Punct('}') if parens == 0 => braces = braces.saturating_sub(1),
+            Token::Name(name) if braces == 0 && parens == 0 => match name.as_str() {
+                "query" => {
+                    ops.push(Operation::Query);
+                    named = true;
+                }
+                "mutation" => {
+                    ops.push(Operation::Mutation);
+                    named = true;
+                }
+                "subscription" => {
+                    ops.push(Operation::Subscription);
+                    named = true;
+                }
+                "fragment" => named = true,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    ops
+}
+
+/// Estimate how many nodes a query can return, the way GitHub does: each
+/// connection contributes its `first`/`last` size multiplied by the sizes of
+/// the connections it is nested in. Fragment spreads are costed where they
+/// are used, under the connections that enclose the spread.
+fn estimate_nodes(tokens: &[Token], variables: &serde_json::Value) -> u64 {
+    let mut estimator = Estimator {
+        tokens,
+        variables,
+        fragments: HashMap::new(),
+        costs: HashMap::new(),
+        expanding: Vec::new(),
+    };
+    let mut operations = Vec::new();
+    let (mut braces, mut parens) = (0usize, 0usize);
+    let (mut fragment, mut start) = (None, 0);
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Punct('(') => parens += 1,
+            Token::Punct(')') => parens = parens.saturating_sub(1),
+            Token::Punct('{') if parens == 0 => {
+                if braces == 0 {
+                    start = i;
+                }
+                braces += 1;
+            }
+            Token::Punct('}') if parens == 0 && braces > 0 => {
+                braces -= 1;
+                if braces == 0 {
+                    match fragment.take() {
+                        Some(name) => {
+                            estimator.fragments.insert(name, start..i + 1);
+                        }
+                        None => operations.push(start..i + 1),
+                    }
+                }
+            }
+            Token::Name(name) if braces == 0 && parens == 0 && name == "fragment" => {
+                if let Some(Token::Name(fragment_name)) = tokens.get(i + 1) {
+                    fragment = Some(fragment_name.as_str());
+                }
+            }
+            _ => {}
+        }
+    }
+    operations.into_iter().fold(0, |total: u64, range| {
+        total.saturating_add(estimator.selection_cost(range))
+    })
+}
+
+struct Estimator<'a> {
+    tokens: &'a [Token],
+    variables: &'a serde_json::Value,
+    /// Token range of each fragment's selection set, braces included.
+    fragments: HashMap<&'a str, Range<usize>>,
+    /// Cost of each fragment when spread outside any connection.
+    costs: HashMap<&'a str, u64>,
+    /// Fragments currently being costed, to catch spread cycles.
+    expanding: Vec<&'a str>,
+}
+
+impl<'a> Estimator<'a> {
+    /// Nodes requested by the selection set in `range`, with a multiplier of 1.
+    fn selection_cost(&mut self, range: Range<usize>) -> u64 {
+        let tokens: &'a [Token] = &self.tokens[range];
+        let mut total: u64 = 0;
+        let mut stack: Vec<u64> = Vec::new();
+        let mut parens = 0usize;
+        let mut page: Option<u64> = None;
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Punct('(') => parens += 1,
+                Token::Punct(')') => parens = parens.saturating_sub(1),
+                Token::Punct('{') if parens == 0 => {
+                    let parent = stack.last().copied().unwrap_or(1);
+                    let multiplier = parent.saturating_mul(page.unwrap_or(1));
+                    if page.is_some() {
+                        total = total.saturating_add(multiplier);
+                    }
+                    stack.push(multiplier);
+                    page = None;
+                }
+                Token::Punct('}') if parens == 0 => {
+                    stack.pop();
+                    page = None;
+                }
+                Token::Name(name) if parens > 0 && (name == "first" || name == "last") => {
+                    if let Some(size) = page_size(&tokens[i + 1..], self.variables) {
+                        page = Some(page.map_or(size, |p| p.max(size)));
+                    }
+                }
+                Token::Spread if parens == 0 => {
+                    if let Some(Token::Name(name)) = tokens.get(i + 1) {
+                        if name != "on" {
+                            let parent = stack.last().copied().unwrap_or(1);
+                            let cost = self.fragment_cost(name);
+                            total = total.saturating_add(parent.saturating_mul(cost));
+                        }
+                    }
+                }
+                // A new field outside an argument list starts a fresh selection.
+                Token::Name(_) if parens == 0 => page = None,
+                _ => {}
+            }
+        }
+        total
+    }
+
+    /// Nodes requested by one spread of fragment `name`. Unknown fragments
+    /// cost nothing (GitHub rejects them); cycles cost the maximum so the
+    /// query is refused.
+    fn fragment_cost(&mut self, name: &'a str) -> u64 {
+        if let Some(&cost) = self.costs.get(name) {
+            return cost;
+        }
+        if self.expanding.contains(&name) {
+            return u64::MAX;
+        }
+        let Some(range) = self.fragments.get(name).cloned() else {
+            return 0;
+        };
+        self.expanding.push(name);
+        let cost = self.selection_cost(range);
+        self.expanding.pop();
+        self.costs.insert(name, cost);
+        cost
+    }
+}
+
+/// The value of a `first`/`last` argument from the tokens following its
+/// name: `: 10` or `: $var`.
+fn page_size(tokens: &[Token], variables: &serde_json::Value) -> Option<u64> {
+    match tokens {
+        [Token::Punct(':'), Token::Int(n), ..] => Some(*n),
+        [Token::Punct(':'), Token::Punct('$'), Token::Name(var), ..] => Some(
+            variables
+                .get(var)
+                .and_then(|v| v.as_u64())
+                .unwrap_or(DEFAULT_PAGE_SIZE),
+        ),
+        _ => None,
+    }
+}
+
+/// Reject queries `graphql_query` should not send: oversized or malformed
+/// documents, subscriptions, mutations unless writes are enabled, and queries whose
+/// estimated node count exceeds [`MAX_NODES`].
+pub fn check_query(
+    query: &str,
+    variables: &serde_json::Value,
+    allow_writes: bool,
+) -> Result<(), McpGithubError> {
+    if query.len() > MAX_QUERY_BYTES {
+        return Err(McpGithubError::LimitExceeded(format!(
+            "query is {} bytes; the limit is {}",
+            query.len(),
+            MAX_QUERY_BYTES
+        )));
+    }
+    let tokens = tokenize(query).map_err(McpGithubError::MissingParam)?;
+    let ops = operations(&tokens);
+    if ops.is_empty() {
+        return Err(McpGithubError::MissingParam(
+            "query does not contain an operation".to_string(),
+        ));
+    }
+    if ops.contains(&Operation::Subscription) {
+        return Err(McpGithubError::MissingParam(
+            "subscriptions are not supported".to_string(),
+        ));
+    }
+    if ops.contains(&Operation::Mutation) && !allow_writes {
+        return Err(McpGithubError::MissingParam(
+            "mutations require the server to be started with --allow-writes".to_string(),
+        ));
+    }
+    let nodes = estimate_nodes(&tokens, variables);
+    if nodes > MAX_NODES {
+        return Err(McpGithubError::LimitExceeded(format!(
+            "query may return up to {} nodes; the limit is {}. Lower first/last values",
+            nodes, MAX_NODES
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(query: &str) -> Vec<Operation> {
+        operations(&tokenize(query).unwrap())
+    }
+
+    fn nodes(query: &str, variables: serde_json::Value) -> u64 {
+        estimate_nodes(&tokenize(query).unwrap(), &variables)
+    }
+
+    #[test]
+    fn test_operations() {
+        assert_eq!(ops("{ viewer { login } }"), vec![Operation::Query]);
+        assert_eq!(
+            ops("query Q($n: Int = 5) { viewer { login } }"),
+            vec![Operation::Query]
+        );
+        assert_eq!(
+            ops(
+                "fragment F on User { login }\n\
+                 mutation M { addStar(input: {starrableId: \"x\"}) { clientMutationId } }"
+            ),
+            vec![Operation::Mutation]
+        );
+        assert_eq!(
+            ops("subscription { x }"),
+            vec![Operation::Subscription]
+        );
+    }
+
+    #[test]
+    fn test_operations_ignore_comments_and_strings() {
+        let query = "# mutation in a comment\n\
+                     query { search(query: \"mutation { x }\", type: ISSUE, first: 1) { issueCount } }";
+        assert_eq!(ops(query), vec![Operation::Query]);
+        let block = "query { a(q: \"\"\"mutation \"quoted\" {\"\"\") { b } }";
+        assert_eq!(ops(block), vec![Operation::Query]);
+    }
+
+    #[test]
+    fn test_estimate_nodes_multiplies_nested_connections() {
+        let query = "query($n: Int!) {
+            repository(owner: \"o\", name: \"r\") {
+                issues(first: 50, filterBy: {states: OPEN}) {
+                    nodes { title labels(first: 10) { nodes { name } } }
+                }
+                pullRequests(last: $n) { nodes { title } }
+            }
+        }";
+        // issues: 50, labels: 50 * 10, pullRequests: 20
+        assert_eq!(nodes(query, serde_json::json!({"n": 20})), 50 + 500 + 20);
+        // Unknown variables assume a full page.
+        assert_eq!(nodes(query, serde_json::json!({})), 50 + 500 + 100);
+    }
+
+    #[test]
+    fn test_estimate_nodes_expands_fragment_spreads() {
+        let query = "{ a(first:100) { ...F } } \
+                     fragment F on X { b(first:100) { c(first:100) { d } } }";
+        assert_eq!(
+            nodes(query, serde_json::json!({})),
+            100 + 100 * 100 + 100 * 100 * 100
+        );
+        // Fragments spreading fragments, and inline fragments.
+        let nested = "query { a(first: 10) { ...F ... on Y { e(first: 2) { f } } } } \
+                      fragment F on X { b(first: 3) { ...G } } \
+                      fragment G on Z { c(first: 4) { d } }";
+        assert_eq!(
+            nodes(nested, serde_json::json!({})),
+            10 + 10 * 3 + 10 * 3 * 4 + 10 * 2
+        );
+        let cycle = "{ a(first: 1) { ...F } } fragment F on X { ...F }";
+        assert_eq!(nodes(cycle, serde_json::json!({})), u64::MAX);
+    }
+
+    #[test]
+    fn test_tokenize_rejects_unterminated_strings_and_comments() {
+        assert!(tokenize("{ a(q: \"\"\"x) { b } }").is_err());
+        assert!(tokenize("{ a(q: \"x) { b } }").is_err());
+        assert!(tokenize("{ a(q: \"x\ny\") { b } }").is_err());
+        assert!(tokenize("{ a { b } } # trailing").is_err());
+        assert!(tokenize("{ a { b } } # trailing\n").is_ok());
+        // In block strings only \""" is an escape, and a backslash before it
+        // is literal, so \\""" does not end the string either.
+        let escaped = "{ a(q: \"\"\"x\\\"\"\" y\"\"\") { b } }";
+        assert_eq!(ops(escaped), vec![Operation::Query]);
+        assert!(tokenize("{ a(q: \"\"\"x\\\\\"\"\") { b } }").is_err());
+    }
+
+    #[test]
+    fn test_comment_ended_by_carriage_return_cannot_hide_mutation() {
+        let payload = "#\rmutation { addStar(input:{starrableId:\"x\", clientMutationId:\"\"\"\n{ viewer { login } }\n\"\"\"}) { clientMutationId } }";
+        assert_eq!(ops(payload), vec![Operation::Mutation]);
+        let err = check_query(payload, &serde_json::json!({}), false).unwrap_err();
+        assert!(err.to_string().contains("--allow-writes"), "{}", err);
+        assert_eq!(
+            ops("# c\r\nmutation { x { y } }"),
+            vec![Operation::Mutation]
+        );
+    }
+
+    #[test]
+    fn test_check_query() {
+        let vars = serde_json::json!({});
+        assert!(check_query("{ viewer { login } }", &vars, false).is_ok());
+        assert!(check_query("mutation { x { y } }", &vars, false).is_err());
+        assert!(check_query("mutation { x { y } }", &vars, true).is_ok());
+        assert!(check_query("subscription { x { y } }", &vars, true).is_err());
+        assert!(check_query("", &vars, false).is_err());
+
+        let expensive = "{ a(first: 100) { b(first: 100) { c(first: 100) { d } } } }";
+        assert!(matches!(
+            check_query(expensive, &vars, false),
+            Err(McpGithubError::LimitExceeded(_))
+        ));
+
+        let fragments = "{ a(first:100) { ...F } } \
+                         fragment F on X { b(first:100) { c(first:100) { d } } }";
+        assert!(check_query(fragments, &vars, false).is_err());
+        assert!(check_query("{ a(q: \"\"\"x) { b } }", &vars, false).is_err());
+
+        let huge = format!("{{ viewer {{ {} }} }}", "login ".repeat(MAX_QUERY_BYTES));
+        assert!(matches!(
+            check_query(&huge, &vars, false),
+            Err(McpGithubError::LimitExceeded(_))
+        ));
+    }
+}
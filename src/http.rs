@@ -0,0 +1,211 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use tokio_util::sync::CancellationToken;
+
+use crate::server::McpGithubServer;
+
+/// Path the MCP Streamable HTTP endpoint is mounted at.
+pub const MCP_PATH: &str = "/mcp";
+
+/// Build an axum router serving `server` over MCP Streamable HTTP.
+///
+/// Each client session gets its own clone of the server, so all sessions
+/// share the same GitHub client and token.
+pub fn router(server: McpGithubServer, ct: CancellationToken) -> axum::Router {
+    let service = StreamableHttpService::new(
+        move || Ok(server.clone()),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig {
+            cancellation_token: ct,
+            ..Default::default()
+        },
+    );
+    axum::Router::new().nest_service(MCP_PATH, service)
+}
+
+/// Serve `server` over Streamable HTTP on `bind` until `ct` is cancelled.
+pub async fn serve(
+    server: McpGithubServer,
+    bind: SocketAddr,
+    ct: CancellationToken,
+) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    tracing::info!(addr = %listener.local_addr()?, path = MCP_PATH, "Listening for MCP over HTTP");
+
+    let app = router(server, ct.child_token());
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { ct.cancelled_owned().await })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    const INITIALIZE: &str = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"test","version":"0.0.0"}}}"#;
+
+    fn make_router() -> axum::Router {
+        let github = octocrab::Octocrab::default();
+        let server = McpGithubServer::new(github, None, 30);
+        router(server, CancellationToken::new())
+    }
+
+    /// Initialize a session on `app` and return its ID.
+    async fn open_session(app: &axum::Router) -> String {
+        let resp = app.clone().oneshot(post(INITIALIZE, None)).await.unwrap();
+        let session = resp.headers()["mcp-session-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        body_text(resp).await;
+
+        let resp = app
+            .clone()
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+                Some(&session),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        session
+    }
+
+    fn post(body: &'static str, session: Option<&str>) -> Request<Body> {
+        let mut req = Request::post(MCP_PATH)
+            .header("accept", "application/json, text/event-stream")
+            .header("content-type", "application/json");
+        if let Some(id) = session {
+            req = req.header("mcp-session-id", id);
+        }
+        req.body(Body::from(body)).unwrap()
+    }
+
+    async fn body_text(resp: axum::response::Response) -> String {
+        let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    /// Send a request on `session` and return the JSON-RPC message carried
+    /// by the SSE response.
+    async fn rpc(app: &axum::Router, session: &str, body: &'static str) -> serde_json::Value {
+        let resp = app
+            .clone()
+            .oneshot(post(body, Some(session)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let text = body_text(resp).await;
+        let data = text
+            .lines()
+            .filter_map(|l| l.strip_prefix("data:"))
+            .map(str::trim)
+            .rfind(|d| !d.is_empty())
+            .unwrap_or_else(|| panic!("no message in {}", text));
+        serde_json::from_str(data).unwrap()
+    }
+
+    /// The JSON body of an `isError` tool result.
+    fn error_result(message: &serde_json::Value) -> serde_json::Value {
+        assert_eq!(message["result"]["isError"], true, "{}", message);
+        let text = message["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_returns_session_id() {
+        let app = make_router();
+        let resp = app.oneshot(post(INITIALIZE, None)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key("mcp-session-id"));
+        assert!(resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/event-stream"));
+
+        let body = body_text(resp).await;
+        assert!(body.contains("\"name\":\"mcp-github\""));
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_over_session() {
+        let app = make_router();
+        let session = open_session(&app).await;
+
+        let resp = app
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+                Some(&session),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = body_text(resp).await;
+        assert!(body.contains("\"get_repo\""));
+    }
+
+    #[tokio::test]
+    async fn test_error_results_over_session() {
+        let policy = crate::policy::RepoPolicy::new(&[], &["o/secret".to_string()]).unwrap();
+        let server = McpGithubServer::new(octocrab::Octocrab::default(), None, 30)
+            .with_repo_policy(policy)
+            .with_error_results(true);
+        let app = router(server, CancellationToken::new());
+        let session = open_session(&app).await;
+
+        let message = rpc(
+            &app,
+            &session,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"get_repo","arguments":{"owner":"o","repo":"secret"}}}"#,
+        )
+        .await;
+        assert_eq!(error_result(&message)["code"], "forbidden");
+
+        // Malformed arguments stay protocol errors.
+        let message = rpc(
+            &app,
+            &session,
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"get_repo","arguments":{"owner":"o","repo":"bad repo"}}}"#,
+        )
+        .await;
+        assert!(message.get("result").is_none(), "{}", message);
+        assert_eq!(message["error"]["data"]["code"], "invalid_params");
+    }
+
+    #[tokio::test]
+    async fn test_limit_errors_are_tool_results() {
+        let server =
+            McpGithubServer::new(octocrab::Octocrab::default(), None, 30).with_error_results(true);
+        let app = router(server, CancellationToken::new());
+        let session = open_session(&app).await;
+
+        let message = rpc(
+            &app,
+            &session,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"graphql_query","arguments":{"query":"{ a(first: 100) { b(first: 100) { c(first: 100) { d } } } }"}}}"#,
+        )
+        .await;
+        assert_eq!(error_result(&message)["code"], "limit_exceeded");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_session_rejected() {
+        let app = make_router();
+        let resp = app
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+                Some("does-not-exist"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,7 +1,18 @@
 //! MCP server that lets LLMs interact with GitHub repositories, issues, and pull requests.
 //!
 //! Provides tools for listing repos, browsing issues and PRs, searching code,
-//! and viewing GitHub Actions workflow runs.
+//! and viewing GitHub Actions workflow runs. The server speaks MCP over stdio
+//! or over Streamable HTTP (see [`http`]).
 
+pub mod cache;
+pub mod client;
+pub mod config;
 pub mod error;
+pub mod graphql;
+pub mod http;
+pub mod pagination;
+pub mod policy;
+pub mod ratelimit;
+pub mod resources;
 pub mod server;
+pub mod toolsets;
@@ -1,13 +1,24 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use anyhow::Result;
-use clap::Parser;
-use mcp_github::server;
+use clap::{Parser, ValueEnum};
+use mcp_github::toolsets::{self, Toolset};
+use mcp_github::{cache::ResponseCache, client, config, http, policy::RepoPolicy, server};
 use rmcp::{transport::stdio, ServiceExt};
+use tokio_util::sync::CancellationToken;
 use tracing_subscriber::EnvFilter;
 
 /// MCP server for GitHub — lets LLMs explore repositories, issues, and pull requests
 #[derive(Parser)]
 #[command(name = "mcp-github", version, about)]
 struct Cli {
+    /// TOML config file. Flags override its values.
+    /// Default: $XDG_CONFIG_HOME/mcp-github/config.toml, if it exists
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
     /// GitHub personal access token.
     /// Can also be set via GITHUB_TOKEN environment variable.
     #[arg(long)]
@@ -18,13 +29,136 @@ struct Cli {
     #[arg(long = "token-env")]
     token_env: Option<String>,
 
+    /// Authenticate as this GitHub App instead of with a token
+    #[arg(long, requires = "app_private_key")]
+    app_id: Option<u64>,
+
+    /// PEM file with the GitHub App's private key
+    #[arg(long, requires = "app_id")]
+    app_private_key: Option<PathBuf>,
+
+    /// GitHub App installation to act as.
+    /// Default: the app's installation on --owner
+    #[arg(long, requires = "app_id")]
+    installation_id: Option<u64>,
+
+    /// GitHub REST API root, e.g. https://ghe.example.com/api/v3 for
+    /// GitHub Enterprise Server. Can also be set via GITHUB_API_URL.
+    /// Default: https://api.github.com
+    #[arg(long)]
+    api_url: Option<String>,
+
+    /// GitHub upload API root. Default: derived from --api-url
+    #[arg(long)]
+    upload_url: Option<String>,
+
+    /// PEM file with additional CA certificates to trust (e.g. an internal CA)
+    #[arg(long)]
+    ca_cert: Option<PathBuf>,
+
     /// Default repository owner/org for operations
     #[arg(long)]
     owner: Option<String>,
 
+    /// Repository (OWNER/REPO) whose files are enumerated as MCP resources
+    #[arg(long, value_name = "OWNER/REPO")]
+    resource_repo: Option<String>,
+
     /// Maximum results per API call (default: 30)
-    #[arg(long, default_value = "30")]
-    max_results: u32,
+    #[arg(long)]
+    max_results: Option<u32>,
+
+    /// Only register tools from these toolsets: repos, issues, pulls,
+    /// actions, search, graphql. Default: all
+    #[arg(long, value_delimiter = ',', value_name = "TOOLSET,...")]
+    toolsets: Option<Vec<Toolset>>,
+
+    /// Do not register these tools, even if their toolset is enabled
+    #[arg(long, value_delimiter = ',', value_name = "TOOL,...")]
+    disable_tools: Option<Vec<String>>,
+
+    /// Only allow access to repositories matching these globs,
+    /// e.g. "myorg/*,other/tool". Default: all
+    #[arg(long, value_delimiter = ',', value_name = "OWNER/REPO,...")]
+    allow_repos: Option<Vec<String>>,
+
+    /// Never allow access to repositories matching these globs
+    #[arg(long, value_delimiter = ',', value_name = "OWNER/REPO,...")]
+    deny_repos: Option<Vec<String>>,
+
+    /// Enable tools that modify GitHub state (create/update issues, comments).
+    /// Without this flag the server is read-only. `--allow-writes=false`
+    /// overrides the config file.
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    allow_writes: Option<bool>,
+
+    /// Report GitHub failures (not found, permissions, rate limits) as tool
+    /// results with isError set and a JSON body the model can read.
+    /// Malformed arguments are still protocol errors.
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    error_results: Option<bool>,
+
+    /// Seconds a cached response is reused without asking GitHub.
+    /// After that it is revalidated with its ETag (304s don't cost rate limit).
+    /// Default: 0
+    #[arg(long)]
+    cache_ttl: Option<u64>,
+
+    /// Also persist cached responses in this directory
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Transport to serve MCP over. Default: stdio
+    #[arg(long, value_enum)]
+    transport: Option<Transport>,
+
+    /// Address to listen on when using the HTTP transport.
+    /// Default: 127.0.0.1:8080
+    #[arg(long)]
+    bind: Option<SocketAddr>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Transport {
+    /// Single client over stdin/stdout
+    Stdio,
+    /// MCP Streamable HTTP, shared by multiple clients
+    Http,
+}
+
+impl From<config::TransportMode> for Transport {
+    fn from(mode: config::TransportMode) -> Self {
+        match mode {
+            config::TransportMode::Stdio => Transport::Stdio,
+            config::TransportMode::Http => Transport::Http,
+        }
+    }
+}
+
+/// Load the config file named by `--config`, or the default one if present.
+fn load_config(path: Option<&Path>) -> Result<config::Config> {
+    if let Some(path) = path {
+        return Ok(config::Config::load(path)?);
+    }
+    match config::default_path() {
+        Some(path) if path.is_file() => {
+            tracing::info!(path = %path.display(), "Loading config file");
+            Ok(config::Config::load(&path)?)
+        }
+        _ => Ok(config::Config::default()),
+    }
 }
 
 #[tokio::main]
@@ -36,11 +170,47 @@ async fn main() -> Result<()> {
         .with_writer(std::io::stderr)
         .init();
 
-    // Resolve token: --token > --token-env > GITHUB_TOKEN
-    let token = if let Some(t) = cli.token {
+    // Flags win over the config file, which wins over built-in defaults.
+    let file = load_config(cli.config.as_deref())?;
+    let owner = cli.owner.or(file.owner);
+    let max_results = cli.max_results.or(file.max_results).unwrap_or(30);
+    let allow_writes = cli.allow_writes.or(file.allow_writes).unwrap_or(false);
+    let error_results = cli.error_results.or(file.error_results).unwrap_or(false);
+    let enabled_toolsets = cli
+        .toolsets
+        .or(file.toolsets)
+        .unwrap_or_else(|| Toolset::ALL.to_vec());
+    let disabled_tools = cli.disable_tools.or(file.disable_tools).unwrap_or_default();
+    toolsets::check_tool_names(&disabled_tools)
+        .map_err(|e| anyhow::anyhow!("--disable-tools: {}", e))?;
+    let repo_policy = RepoPolicy::new(
+        &cli.allow_repos.or(file.allow_repos).unwrap_or_default(),
+        &cli.deny_repos.or(file.deny_repos).unwrap_or_default(),
+    )
+    .map_err(|e| anyhow::anyhow!("--allow-repos/--deny-repos: {}", e))?;
+    let app_id = cli.app_id.or(file.app.id);
+    let app_private_key = cli.app_private_key.or(file.app.private_key);
+    let installation_id = cli.installation_id.or(file.app.installation_id);
+    let transport = cli
+        .transport
+        .or(file.transport.mode.map(Transport::from))
+        .unwrap_or(Transport::Stdio);
+    let bind = cli
+        .bind
+        .or(file.transport.bind)
+        .unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 8080)));
+
+    // Resolve token: --token > --token-env > config token_env > GITHUB_TOKEN
+    let token = if app_id.is_some() {
+        None
+    } else if let Some(t) = cli.token {
         Some(t)
     } else {
-        let env_name = cli.token_env.as_deref().unwrap_or("GITHUB_TOKEN");
+        let env_name = cli
+            .token_env
+            .as_deref()
+            .or(file.token_env.as_deref())
+            .unwrap_or("GITHUB_TOKEN");
         match std::env::var(env_name) {
             Ok(t) if !t.is_empty() => {
                 tracing::info!(env = env_name, "Read GitHub token from environment variable");
@@ -50,28 +220,91 @@ async fn main() -> Result<()> {
         }
     };
 
-    let github = if let Some(ref t) = token {
-        octocrab::OctocrabBuilder::new()
-            .personal_token(t.clone())
-            .build()
-            .map_err(|e| anyhow::anyhow!("Failed to create GitHub client: {}", e))?
-    } else {
-        tracing::warn!("No GitHub token provided — API rate limits will be very restrictive");
-        octocrab::Octocrab::default()
+    // Resolve API root: --api-url > config file > GITHUB_API_URL > api.github.com
+    let api_url = cli.api_url.or(file.api_url).or_else(|| {
+        std::env::var("GITHUB_API_URL")
+            .ok()
+            .filter(|u| !u.is_empty())
+    });
+
+    let credentials = match (app_id, app_private_key, token) {
+        (Some(app_id), Some(private_key), _) => client::Credentials::App(client::AppCredentials {
+            app_id,
+            private_key,
+            installation_id,
+            owner: owner.clone(),
+        }),
+        (_, _, Some(t)) => client::Credentials::Token(t),
+        _ => {
+            tracing::warn!("No GitHub token provided — API rate limits will be very restrictive");
+            client::Credentials::Anonymous
+        }
     };
+    let authenticated = !matches!(credentials, client::Credentials::Anonymous);
 
-    let authenticated = token.is_some();
+    let options = client::ClientOptions {
+        credentials,
+        api_url,
+        upload_url: cli.upload_url.or(file.upload_url),
+        ca_cert: cli.ca_cert.or(file.ca_cert),
+    };
+    let github = client::build(&options)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create GitHub client: {}", e))?;
 
     tracing::info!(
         authenticated,
-        owner = cli.owner.as_deref().unwrap_or("none"),
-        max_results = cli.max_results,
+        api_url = options.api_url(),
+        owner = owner.as_deref().unwrap_or("none"),
+        max_results,
+        allow_writes,
+        transport = ?transport,
         "Starting mcp-github server"
     );
 
-    let service = server::McpGithubServer::new(github, cli.owner, cli.max_results);
-    let running = service.serve(stdio()).await?;
-    running.waiting().await?;
+    let mut service = server::McpGithubServer::new(github, owner, max_results)
+        .with_graphql_url(options.graphql_url())
+        .with_toolsets(enabled_toolsets, disabled_tools)
+        .with_repo_policy(repo_policy)
+        .with_error_results(error_results)
+        .with_writes(allow_writes)
+        .with_cache(
+            ResponseCache::new(
+                Duration::from_secs(cli.cache_ttl.or(file.cache.ttl).unwrap_or(0)),
+                cli.cache_dir.or(file.cache.dir),
+            )
+            .with_scope(options.cache_scope()),
+        );
+    if let Some(ref full_name) = cli.resource_repo.or(file.resource_repo) {
+        let (owner, repo) = config::split_full_name(full_name)
+            .map_err(|e| anyhow::anyhow!("--resource-repo {}", e))?;
+        service = service.with_resource_repo(owner.to_string(), repo.to_string());
+    }
+    match transport {
+        Transport::Stdio => {
+            let running = service.clone().serve(stdio()).await?;
+            running.waiting().await?;
+        }
+        Transport::Http => {
+            let ct = CancellationToken::new();
+            tokio::spawn({
+                let ct = ct.clone();
+                async move {
+                    let _ = tokio::signal::ctrl_c().await;
+                    ct.cancel();
+                }
+            });
+            http::serve(service.clone(), bind, ct).await?;
+        }
+    }
+
+    let stats = service.cache().stats();
+    tracing::info!(
+        hits = stats.hits,
+        revalidated = stats.revalidated,
+        misses = stats.misses,
+        "Response cache statistics"
+    );
 
     Ok(())
 }
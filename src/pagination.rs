@@ -0,0 +1,248 @@
+use std::future::Future;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use octocrab::Page;
+
+use crate::error::McpGithubError;
+
+/// Upper bound on the number of items a single list call may return when
+/// following `Link: rel=next` headers.
+pub const MAX_LIST_LIMIT: usize = 1000;
+
+/// Where a list call starts reading and how much it may return.
+///
+/// GitHub's REST list endpoints paginate by page number, so a position is a
+/// page, the page size it was computed with, and how many items of that page
+/// were already returned. The position is handed to clients as an opaque
+/// cursor string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRequest {
+    pub page: u32,
+    pub per_page: u8,
+    pub skip: usize,
+    pub limit: usize,
+}
+
+impl PageRequest {
+    /// Build a request from tool parameters. A cursor takes precedence over
+    /// `page` and pins the page size it was created with, so resumed reads
+    /// stay aligned. A page size of 0 is raised to 1.
+    pub fn from_params(
+        per_page: u8,
+        page: Option<u32>,
+        cursor: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<Self, McpGithubError> {
+        let (page, per_page, skip) = match cursor {
+            Some(c) => decode_cursor(c)?,
+            None => (page.unwrap_or(1).max(1), per_page.max(1), 0),
+        };
+        let limit = limit
+            .map(|l| l as usize)
+            .unwrap_or(per_page as usize)
+            .clamp(1, MAX_LIST_LIMIT);
+        Ok(Self {
+            page,
+            per_page,
+            skip,
+            limit,
+        })
+    }
+}
+
+/// Encode a resume position as an opaque cursor.
+pub fn encode_cursor(page: u32, per_page: u8, skip: usize) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}:{}:{}", page, per_page, skip))
+}
+
+/// Decode a cursor produced by [`encode_cursor`].
+pub fn decode_cursor(cursor: &str) -> Result<(u32, u8, usize), McpGithubError> {
+    let invalid = || McpGithubError::MissingParam("cursor is invalid or expired".to_string());
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+    let raw = String::from_utf8(bytes).map_err(|_| invalid())?;
+    let mut parts = raw.splitn(3, ':');
+    let page: u32 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(invalid)?;
+    let per_page: u8 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(invalid)?;
+    let skip: usize = parts.next().and_then(|p| p.parse().ok()).ok_or_else(invalid)?;
+    if page == 0 || per_page == 0 || per_page > 100 || skip >= per_page as usize {
+        return Err(invalid());
+    }
+    Ok((page, per_page, skip))
+}
+
+/// Collect items starting from `first` (the page `req.page`), following
+/// `rel=next` links with `fetch_next` until `req.limit` items are gathered or
+/// the results run out. Returns the items and a cursor for the next unread
+/// item, if any.
+pub async fn collect_pages<T, F, Fut>(
+    req: &PageRequest,
+    first: Page<T>,
+    fetch_next: F,
+) -> Result<(Vec<T>, Option<String>), McpGithubError>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Page<T>, McpGithubError>>,
+{
+    let mut items = Vec::new();
+    let mut page_no = req.page;
+    let mut skip = req.skip;
+    let mut current = Some(first);
+
+    while let Some(page) = current {
+        let next = page.next;
+        let page_items: Vec<T> = page.items.into_iter().skip(skip).collect();
+        let room = req.limit - items.len();
+
+        if page_items.len() > room {
+            // Stop mid-page; resume from the first item not returned.
+            items.extend(page_items.into_iter().take(room));
+            let cursor = encode_cursor(page_no, req.per_page, skip + room);
+            return Ok((items, Some(cursor)));
+        }
+
+        items.extend(page_items);
+        skip = 0;
+        let Some(uri) = next else {
+            break;
+        };
+        if items.len() >= req.limit {
+            return Ok((items, Some(encode_cursor(page_no + 1, req.per_page, 0))));
+        }
+        current = Some(fetch_next(uri.to_string()).await?);
+        page_no += 1;
+    }
+
+    Ok((items, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = encode_cursor(3, 50, 20);
+        assert_eq!(decode_cursor(&cursor).unwrap(), (3, 50, 20));
+    }
+
+    #[test]
+    fn test_cursor_rejects_garbage() {
+        assert!(decode_cursor("not a cursor!").is_err());
+        assert!(decode_cursor(&URL_SAFE_NO_PAD.encode("1:2")).is_err());
+        assert!(decode_cursor(&URL_SAFE_NO_PAD.encode("0:30:0")).is_err());
+        assert!(decode_cursor(&URL_SAFE_NO_PAD.encode("1:200:0")).is_err());
+        assert!(decode_cursor(&URL_SAFE_NO_PAD.encode("1:30:30")).is_err());
+    }
+
+    #[test]
+    fn test_page_request_defaults() {
+        let req = PageRequest::from_params(30, None, None, None).unwrap();
+        assert_eq!(
+            req,
+            PageRequest {
+                page: 1,
+                per_page: 30,
+                skip: 0,
+                limit: 30
+            }
+        );
+    }
+
+    #[test]
+    fn test_page_request_zero_page_size() {
+        let req = PageRequest::from_params(0, None, None, None).unwrap();
+        assert_eq!(req.per_page, 1);
+        assert_eq!(req.limit, 1);
+    }
+
+    #[test]
+    fn test_page_request_explicit_page_and_limit() {
+        let req = PageRequest::from_params(100, Some(4), None, Some(250)).unwrap();
+        assert_eq!(req.page, 4);
+        assert_eq!(req.limit, 250);
+    }
+
+    #[test]
+    fn test_page_request_limit_ceiling() {
+        let req = PageRequest::from_params(100, None, None, Some(1_000_000)).unwrap();
+        assert_eq!(req.limit, MAX_LIST_LIMIT);
+    }
+
+    #[test]
+    fn test_page_request_cursor_overrides_page() {
+        let cursor = encode_cursor(5, 25, 10);
+        let req = PageRequest::from_params(100, Some(2), Some(&cursor), None).unwrap();
+        assert_eq!(req.page, 5);
+        assert_eq!(req.per_page, 25);
+        assert_eq!(req.skip, 10);
+    }
+
+    async fn no_fetch(uri: String) -> Result<Page<u32>, McpGithubError> {
+        panic!("unexpected fetch of {}", uri)
+    }
+
+    fn page(items: Vec<u32>, has_next: bool) -> Page<u32> {
+        let mut page = Page::<u32>::default();
+        page.items = items;
+        if has_next {
+            page.next = Some("https://api.github.com/next".parse().unwrap());
+        }
+        page
+    }
+
+    #[tokio::test]
+    async fn test_collect_single_page() {
+        let req = PageRequest::from_params(3, None, None, None).unwrap();
+        let (items, cursor) = collect_pages(&req, page(vec![1, 2, 3], true), no_fetch)
+            .await
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(cursor, Some(encode_cursor(2, 3, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_collect_last_page_has_no_cursor() {
+        let req = PageRequest::from_params(3, None, None, None).unwrap();
+        let (items, cursor) = collect_pages(&req, page(vec![1, 2], false), no_fetch)
+            .await
+            .unwrap();
+        assert_eq!(items, vec![1, 2]);
+        assert_eq!(cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_stops_mid_page() {
+        let req = PageRequest::from_params(5, Some(2), None, Some(3)).unwrap();
+        let (items, cursor) = collect_pages(&req, page(vec![1, 2, 3, 4, 5], true), no_fetch)
+            .await
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(cursor, Some(encode_cursor(2, 5, 3)));
+    }
+
+    #[tokio::test]
+    async fn test_collect_follows_next_links() {
+        let req = PageRequest::from_params(2, None, None, Some(5)).unwrap();
+        let fetch = |uri: String| async move {
+            assert_eq!(uri, "https://api.github.com/next");
+            Ok(page(vec![3, 4], false))
+        };
+        let (items, cursor) = collect_pages(&req, page(vec![1, 2], true), fetch)
+            .await
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3, 4]);
+        assert_eq!(cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_resumes_with_skip() {
+        let req = PageRequest::from_params(5, None, Some(&encode_cursor(2, 5, 3)), Some(5))
+            .unwrap();
+        let (items, cursor) = collect_pages(&req, page(vec![1, 2, 3, 4, 5], false), no_fetch)
+            .await
+            .unwrap();
+        assert_eq!(items, vec![4, 5]);
+        assert_eq!(cursor, None);
+    }
+}
@@ -0,0 +1,189 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::error::McpGithubError;
+
+/// Which repositories tools may touch, from `--allow-repos` and
+/// `--deny-repos` glob lists matched against `owner/repo`.
+///
+/// An empty allow list allows every repository; a deny match always wins.
+/// Matching is case-insensitive, like GitHub names.
+#[derive(Debug, Clone, Default)]
+pub struct RepoPolicy {
+    allow: Option<GlobSet>,
+    deny: Option<GlobSet>,
+    /// Owner halves of the allow patterns.
+    allow_owners: Option<GlobSet>,
+    /// Owners whose every repository is denied (`owner/*`).
+    deny_owners: Option<GlobSet>,
+}
+
+impl RepoPolicy {
+    pub fn new(allow: &[String], deny: &[String]) -> Result<Self, String> {
+        let allow_set = build_set(allow)?;
+        let deny_set = build_set(deny)?;
+        let owner_of = |p: &String| p.split('/').next().unwrap_or_default().to_string();
+        let allow_owners: Vec<String> = allow.iter().map(owner_of).collect();
+        let deny_owners: Vec<String> = deny
+            .iter()
+            .filter(|p| p.ends_with("/*") || p.ends_with("/**"))
+            .map(owner_of)
+            .collect();
+        Ok(Self {
+            allow: allow_set,
+            deny: deny_set,
+            allow_owners: build_owner_set(&allow_owners)?,
+            deny_owners: build_owner_set(&deny_owners)?,
+        })
+    }
+
+    /// Whether any restriction is configured.
+    pub fn is_restricted(&self) -> bool {
+        self.allow.is_some() || self.deny.is_some()
+    }
+
+    /// Whether `full_name` (`owner/repo`) may be accessed.
+    pub fn allows(&self, full_name: &str) -> bool {
+        if self.deny.as_ref().is_some_and(|d| d.is_match(full_name)) {
+            return false;
+        }
+        self.allow.as_ref().map_or(true, |a| a.is_match(full_name))
+    }
+
+    /// Whether some repository of `owner` may be allowed, for searches
+    /// scoped to a whole user or organization.
+    pub fn allows_owner(&self, owner: &str) -> bool {
+        if self.deny_owners.as_ref().is_some_and(|d| d.is_match(owner)) {
+            return false;
+        }
+        self.allow_owners
+            .as_ref()
+            .map_or(true, |a| a.is_match(owner))
+    }
+
+    /// Fail with [`McpGithubError::Forbidden`] unless `owner` is allowed
+    /// (see [`Self::allows_owner`]).
+    pub fn check_owner(&self, owner: &str) -> Result<(), McpGithubError> {
+        if self.allows_owner(owner) {
+            Ok(())
+        } else {
+            Err(McpGithubError::Forbidden(format!(
+                "access to {} is not allowed by the server's repository policy",
+                owner
+            )))
+        }
+    }
+
+    /// Fail with [`McpGithubError::Forbidden`] unless `owner/repo` is allowed.
+    pub fn check(&self, owner: &str, repo: &str) -> Result<(), McpGithubError> {
+        let full_name = format!("{}/{}", owner, repo);
+        if self.allows(&full_name) {
+            Ok(())
+        } else {
+            Err(McpGithubError::Forbidden(format!(
+                "access to {} is not allowed by the server's repository policy",
+                full_name
+            )))
+        }
+    }
+}
+
+/// Compile `OWNER/REPO` glob patterns; `None` for an empty list.
+fn build_set(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        if pattern.matches('/').count() != 1 {
+            return Err(format!("'{}' must look like OWNER/REPO", pattern));
+        }
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("'{}' is not a valid glob: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map(Some).map_err(|e| e.to_string())
+}
+
+/// Compile the owner halves of `OWNER/REPO` patterns; `None` for an empty list.
+fn build_owner_set(owners: &[String]) -> Result<Option<GlobSet>, String> {
+    if owners.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for owner in owners {
+        let glob = GlobBuilder::new(owner)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("'{}' is not a valid glob: {}", owner, e))?;
+        builder.add(glob);
+    }
+    builder.build().map(Some).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allow: &[&str], deny: &[&str]) -> RepoPolicy {
+        let to_vec = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        RepoPolicy::new(&to_vec(allow), &to_vec(deny)).unwrap()
+    }
+
+    #[test]
+    fn test_unrestricted_allows_everything() {
+        let p = RepoPolicy::default();
+        assert!(!p.is_restricted());
+        assert!(p.allows("any/repo"));
+    }
+
+    #[test]
+    fn test_allow_and_deny() {
+        let p = policy(&["myorg/*", "other/tool"], &["myorg/secrets*"]);
+        assert!(p.is_restricted());
+        assert!(p.allows("myorg/api"));
+        assert!(p.allows("MyOrg/API"));
+        assert!(p.allows("other/tool"));
+        assert!(!p.allows("other/tools"));
+        assert!(!p.allows("myorg/secrets-prod"));
+        assert!(!p.allows("evil/api"));
+
+        let err = p.check("myorg", "secrets").unwrap_err();
+        assert!(matches!(err, McpGithubError::Forbidden(_)));
+        assert!(err.to_string().contains("myorg/secrets"));
+    }
+
+    #[test]
+    fn test_deny_only() {
+        let p = policy(&[], &["*/infra"]);
+        assert!(p.allows("myorg/api"));
+        assert!(!p.allows("myorg/infra"));
+        assert!(p.allows_owner("myorg"));
+    }
+
+    #[test]
+    fn test_owners() {
+        let p = policy(&["myorg/*", "other/tool"], &["legacy/*", "myorg/secrets"]);
+        assert!(p.allows_owner("MyOrg"));
+        assert!(p.allows_owner("other"));
+        assert!(!p.allows_owner("evil"));
+        assert!(p.check_owner("myorg").is_ok());
+        assert!(matches!(
+            p.check_owner("evil"),
+            Err(McpGithubError::Forbidden(_))
+        ));
+
+        let deny = policy(&[], &["legacy/*"]);
+        assert!(!deny.allows_owner("legacy"));
+        assert!(deny.allows_owner("myorg"));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(RepoPolicy::new(&["myorg".to_string()], &[]).is_err());
+        assert!(RepoPolicy::new(&[], &["a/b/c".to_string()]).is_err());
+        assert!(RepoPolicy::new(&["myorg/[".to_string()], &[]).is_err());
+    }
+}
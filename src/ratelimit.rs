@@ -0,0 +1,336 @@
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use http::{HeaderMap, StatusCode};
+
+use crate::error::McpGithubError;
+
+/// How many times a call is retried after hitting a secondary rate limit.
+pub const MAX_RETRIES: u32 = 3;
+
+/// Longest we are willing to sleep before a retry. If GitHub asks for a
+/// longer pause the error is returned to the caller instead.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Rate-limit state reported in `x-ratelimit-*` response headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    pub limit: Option<u64>,
+    pub remaining: u64,
+    pub reset_at: DateTime<Utc>,
+    pub resource: Option<String>,
+}
+
+impl RateLimitInfo {
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let remaining = header_u64(headers, "x-ratelimit-remaining")?;
+        let reset = header_u64(headers, "x-ratelimit-reset")?;
+        Some(Self {
+            limit: header_u64(headers, "x-ratelimit-limit"),
+            remaining,
+            reset_at: DateTime::from_timestamp(reset as i64, 0)?,
+            resource: headers
+                .get("x-ratelimit-resource")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from),
+        })
+    }
+}
+
+/// Parse a `retry-after` header given in seconds.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    header_u64(headers, "retry-after").map(Duration::from_secs)
+}
+
+/// Classify a failed response as a primary or secondary rate limit using its
+/// headers and error message. Returns `None` for unrelated failures.
+pub fn classify_response(
+    status: StatusCode,
+    headers: &HeaderMap,
+    message: &str,
+) -> Option<McpGithubError> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    if let Some(wait) = retry_after(headers) {
+        return Some(McpGithubError::SecondaryRateLimited {
+            retry_after: Some(wait.as_secs()),
+        });
+    }
+    if let Some(info) = RateLimitInfo::from_headers(headers) {
+        if info.remaining == 0 {
+            return Some(McpGithubError::RateLimited {
+                reset_at: info.reset_at,
+            });
+        }
+    }
+    if is_secondary_message(message) || status == StatusCode::TOO_MANY_REQUESTS {
+        return Some(McpGithubError::SecondaryRateLimited { retry_after: None });
+    }
+    None
+}
+
+/// What kind of rate limit an octocrab error represents, judged from its
+/// status and message only (typed API calls don't expose headers).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttle {
+    Primary,
+    Secondary,
+}
+
+pub fn classify_error(err: &octocrab::Error) -> Option<Throttle> {
+    let octocrab::Error::GitHub { source, .. } = err else {
+        return None;
+    };
+    let status = source.status_code;
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    if is_secondary_message(&source.message) {
+        Some(Throttle::Secondary)
+    } else if source
+        .message
+        .to_lowercase()
+        .contains("rate limit exceeded")
+    {
+        Some(Throttle::Primary)
+    } else if status == StatusCode::TOO_MANY_REQUESTS {
+        Some(Throttle::Secondary)
+    } else {
+        None
+    }
+}
+
+/// Delay before retry number `attempt` (0-based): GitHub's `retry-after`
+/// hint if given, otherwise exponential backoff from one second. `None`
+/// means the wait would exceed [`MAX_BACKOFF`].
+pub fn backoff_delay(attempt: u32, retry_after: Option<u64>) -> Option<Duration> {
+    let delay = match retry_after {
+        Some(secs) => Duration::from_secs(secs),
+        None => Duration::from_secs(1u64 << attempt.min(6)),
+    };
+    (delay <= MAX_BACKOFF).then_some(delay)
+}
+
+/// Run `call`, retrying with backoff while GitHub reports a secondary rate
+/// limit. Other results, including primary rate-limit exhaustion, are
+/// returned as-is since retrying cannot help before the reset.
+pub async fn with_retry<T, F, Fut>(mut call: F) -> Result<T, McpGithubError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, McpGithubError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match call().await {
+            Err(McpGithubError::SecondaryRateLimited { retry_after }) if attempt < MAX_RETRIES => {
+                let Some(delay) = backoff_delay(attempt, retry_after) else {
+                    return Err(McpGithubError::SecondaryRateLimited { retry_after });
+                };
+                tracing::warn!(
+                    attempt = attempt + 1,
+                    delay_secs = delay.as_secs(),
+                    "Secondary rate limit hit, backing off"
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Pick the reset time of an exhausted resource from a `/rate_limit`
+/// response body, preferring the one that resets last.
+pub fn exhausted_reset(body: &serde_json::Value) -> Option<DateTime<Utc>> {
+    body.get("resources")?
+        .as_object()?
+        .values()
+        .filter(|r| r.get("remaining").and_then(|v| v.as_u64()) == Some(0))
+        .filter_map(|r| r.get("reset").and_then(|v| v.as_i64()))
+        .max()
+        .and_then(|reset| DateTime::from_timestamp(reset, 0))
+}
+
+fn is_secondary_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("secondary rate limit") || message.contains("abuse detection")
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (k, v) in pairs {
+            map.insert(*k, v.parse().unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_rate_limit_info_from_headers() {
+        let info = RateLimitInfo::from_headers(&headers(&[
+            ("x-ratelimit-limit", "5000"),
+            ("x-ratelimit-remaining", "42"),
+            ("x-ratelimit-reset", "1700000000"),
+            ("x-ratelimit-resource", "core"),
+        ]))
+        .unwrap();
+        assert_eq!(info.limit, Some(5000));
+        assert_eq!(info.remaining, 42);
+        assert_eq!(info.reset_at.timestamp(), 1_700_000_000);
+        assert_eq!(info.resource.as_deref(), Some("core"));
+    }
+
+    #[test]
+    fn test_rate_limit_info_missing_headers() {
+        assert!(RateLimitInfo::from_headers(&HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_classify_primary_exhausted() {
+        let err = classify_response(
+            StatusCode::FORBIDDEN,
+            &headers(&[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000000"),
+            ]),
+            "API rate limit exceeded for user ID 1.",
+        );
+        match err {
+            Some(McpGithubError::RateLimited { reset_at }) => {
+                assert_eq!(reset_at.timestamp(), 1_700_000_000)
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_classify_retry_after_is_secondary() {
+        let err = classify_response(
+            StatusCode::FORBIDDEN,
+            &headers(&[("retry-after", "30"), ("x-ratelimit-remaining", "10")]),
+            "You have exceeded a secondary rate limit.",
+        );
+        assert!(matches!(
+            err,
+            Some(McpGithubError::SecondaryRateLimited {
+                retry_after: Some(30)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_classify_secondary_by_message() {
+        let err = classify_response(
+            StatusCode::FORBIDDEN,
+            &HeaderMap::new(),
+            "You have exceeded a secondary rate limit.",
+        );
+        assert!(matches!(
+            err,
+            Some(McpGithubError::SecondaryRateLimited { retry_after: None })
+        ));
+    }
+
+    #[test]
+    fn test_classify_plain_forbidden_is_not_rate_limit() {
+        let err = classify_response(
+            StatusCode::FORBIDDEN,
+            &headers(&[("x-ratelimit-remaining", "4999")]),
+            "Resource not accessible by integration",
+        );
+        assert!(err.is_none());
+        assert!(classify_response(StatusCode::NOT_FOUND, &HeaderMap::new(), "").is_none());
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(0, None), Some(Duration::from_secs(1)));
+        assert_eq!(backoff_delay(2, None), Some(Duration::from_secs(4)));
+        assert_eq!(backoff_delay(0, Some(10)), Some(Duration::from_secs(10)));
+        assert_eq!(backoff_delay(0, Some(3600)), None);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_retries_secondary() {
+        let mut calls = 0;
+        let result = with_retry(|| {
+            calls += 1;
+            let n = calls;
+            async move {
+                if n < 3 {
+                    Err(McpGithubError::SecondaryRateLimited {
+                        retry_after: Some(0),
+                    })
+                } else {
+                    Ok(n)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_gives_up() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_retry(|| {
+            calls += 1;
+            async {
+                Err(McpGithubError::SecondaryRateLimited {
+                    retry_after: Some(0),
+                })
+            }
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(McpGithubError::SecondaryRateLimited { .. })
+        ));
+        assert_eq!(calls, MAX_RETRIES + 1);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_does_not_retry_long_waits_or_other_errors() {
+        let mut calls = 0;
+        let _: Result<(), _> = with_retry(|| {
+            calls += 1;
+            async {
+                Err(McpGithubError::SecondaryRateLimited {
+                    retry_after: Some(3600),
+                })
+            }
+        })
+        .await;
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let _: Result<(), _> = with_retry(|| {
+            calls += 1;
+            async { Err(McpGithubError::Other("boom".to_string())) }
+        })
+        .await;
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_exhausted_reset() {
+        let body = serde_json::json!({
+            "resources": {
+                "core": {"limit": 5000, "remaining": 0, "reset": 1700000100},
+                "search": {"limit": 30, "remaining": 0, "reset": 1700000050},
+                "graphql": {"limit": 5000, "remaining": 10, "reset": 1700009999},
+            }
+        });
+        assert_eq!(exhausted_reset(&body).unwrap().timestamp(), 1_700_000_100);
+        assert!(exhausted_reset(&serde_json::json!({"resources": {}})).is_none());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::error::McpGithubError;
+
+/// URI template for repository files, advertised via `resources/templates/list`.
+pub const BLOB_URI_TEMPLATE: &str = "github://{owner}/{repo}/blob/{ref}/{path}";
+
+/// Number of resources returned per `resources/list` page.
+pub const RESOURCES_PAGE_SIZE: usize = 200;
+
+const SCHEME: &str = "github://";
+
+/// A file in a repository at a given ref, addressed as
+/// `github://{owner}/{repo}/blob/{ref}/{path}`.
+///
+/// The ref is a single URI segment, so refs containing `/` (such as
+/// `feature/x`) are written percent-encoded (`feature%2Fx`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobUri {
+    pub owner: String,
+    pub repo: String,
+    pub git_ref: String,
+    pub path: String,
+}
+
+impl BlobUri {
+    pub fn parse(uri: &str) -> Result<Self, McpGithubError> {
+        let invalid = || {
+            McpGithubError::MissingParam(format!(
+                "resource URI must look like {}, got '{}'",
+                BLOB_URI_TEMPLATE, uri
+            ))
+        };
+        let rest = uri.strip_prefix(SCHEME).ok_or_else(invalid)?;
+        let mut parts = rest.splitn(5, '/');
+        let owner = parts.next().filter(|s| !s.is_empty()).ok_or_else(invalid)?;
+        let repo = parts.next().filter(|s| !s.is_empty()).ok_or_else(invalid)?;
+        if parts.next() != Some("blob") {
+            return Err(invalid());
+        }
+        let git_ref = parts.next().filter(|s| !s.is_empty()).ok_or_else(invalid)?;
+        let path = parts.next().filter(|s| !s.is_empty()).ok_or_else(invalid)?;
+        Ok(Self {
+            owner: percent_decode(owner).ok_or_else(invalid)?,
+            repo: percent_decode(repo).ok_or_else(invalid)?,
+            git_ref: percent_decode(git_ref).ok_or_else(invalid)?,
+            path: percent_decode(path).ok_or_else(invalid)?,
+        })
+    }
+
+    pub fn to_uri(&self) -> String {
+        format!(
+            "{}{}/{}/blob/{}/{}",
+            SCHEME,
+            self.owner,
+            self.repo,
+            percent_encode(&self.git_ref, true),
+            percent_encode(&self.path, false)
+        )
+    }
+}
+
+/// Best-effort MIME type from a file name, for clients that render by type.
+pub fn mime_type(path: &str) -> &'static str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let ext = name.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("md" | "markdown") => "text/markdown",
+        Some("json") => "application/json",
+        Some("yml" | "yaml") => "application/yaml",
+        Some("toml") => "application/toml",
+        Some("html" | "htm") => "text/html",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        _ => "text/plain",
+    }
+}
+
+/// Escape characters that cannot appear verbatim in a URI segment. Slashes
+/// are kept unless `encode_slash` is set.
+pub(crate) fn percent_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'/' if !encode_slash => out.push('/'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blob_uri() {
+        let uri = BlobUri::parse("github://rust-lang/rust/blob/master/src/README.md").unwrap();
+        assert_eq!(uri.owner, "rust-lang");
+        assert_eq!(uri.repo, "rust");
+        assert_eq!(uri.git_ref, "master");
+        assert_eq!(uri.path, "src/README.md");
+    }
+
+    #[test]
+    fn test_blob_uri_roundtrip_with_slash_ref_and_spaces() {
+        let uri = BlobUri {
+            owner: "o".to_string(),
+            repo: "r".to_string(),
+            git_ref: "feature/x".to_string(),
+            path: "docs/my file.md".to_string(),
+        };
+        let s = uri.to_uri();
+        assert_eq!(s, "github://o/r/blob/feature%2Fx/docs/my%20file.md");
+        assert_eq!(BlobUri::parse(&s).unwrap(), uri);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        assert!(BlobUri::parse("https://github.com/o/r/blob/main/x").is_err());
+        assert!(BlobUri::parse("github://o/r/tree/main/x").is_err());
+        assert!(BlobUri::parse("github://o/r/blob/main").is_err());
+        assert!(BlobUri::parse("github://o/r/blob/main/%zz").is_err());
+    }
+
+    #[test]
+    fn test_mime_type() {
+        assert_eq!(mime_type("README.md"), "text/markdown");
+        assert_eq!(mime_type("src/lib.rs"), "text/plain");
+        assert_eq!(mime_type("img/Logo.PNG"), "image/png");
+        assert_eq!(mime_type("Makefile"), "text/plain");
+    }
+}
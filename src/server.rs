@@ -1,28 +1,77 @@
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 
+use rmcp::handler::server::router::prompt::PromptRouter;
 use rmcp::handler::server::router::tool::ToolRouter;
+use rmcp::handler::server::tool::ToolCallContext;
 use rmcp::handler::server::wrapper::Parameters;
 use rmcp::model::*;
-use rmcp::{schemars, tool, tool_handler, tool_router, ServerHandler};
-use serde::Deserialize;
-
+use rmcp::service::RequestContext;
+use rmcp::{
+    prompt, prompt_handler, prompt_router, schemars, tool, tool_router, RoleServer, ServerHandler,
+};
+use http_body_util::BodyExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{self, ResponseCache};
 use crate::error::McpGithubError;
+use crate::graphql;
+use crate::pagination::{self, PageRequest, MAX_LIST_LIMIT};
+use crate::policy::RepoPolicy;
+use crate::ratelimit::{self, Throttle};
+use crate::resources::{self, BlobUri, RESOURCES_PAGE_SIZE};
+use crate::toolsets::{self, Toolset};
 
 #[derive(Clone)]
 pub struct McpGithubServer {
     github: Arc<octocrab::Octocrab>,
+    graphql_url: String,
+    cache: Arc<ResponseCache>,
     default_owner: Option<String>,
     max_results: u32,
+    allow_writes: bool,
+    resource_repo: Option<(String, String)>,
+    repo_policy: Arc<RepoPolicy>,
+    error_results: bool,
+    toolsets: Vec<Toolset>,
+    disabled_tools: Vec<String>,
     tool_router: ToolRouter<Self>,
+    prompt_router: PromptRouter<Self>,
 }
 
 // -- Tool parameter types --
 
+/// Where a list tool starts reading and how many results it returns.
+/// Flattened into the params of every paginated list tool.
+#[derive(Debug, Default, Deserialize, schemars::JsonSchema)]
+pub struct PageParams {
+    #[schemars(description = "Page number to start from (default: 1)")]
+    #[serde(default)]
+    pub page: Option<u32>,
+
+    #[schemars(description = "Opaque cursor from a previous call's next_cursor; takes precedence over page")]
+    #[serde(default)]
+    pub cursor: Option<String>,
+
+    #[schemars(description = "Total number of results to return, following further pages as needed (max 1000)")]
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
-pub struct OwnerParam {
+pub struct ListReposParams {
     #[schemars(description = "GitHub user or organization name")]
     #[serde(default)]
     pub owner: Option<String>,
+
+    #[schemars(description = "Maximum number of results")]
+    #[serde(default)]
+    pub per_page: Option<u32>,
+
+    #[serde(flatten)]
+    pub paging: PageParams,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -55,6 +104,9 @@ pub struct ListIssuesParams {
     #[schemars(description = "Maximum number of results")]
     #[serde(default)]
     pub per_page: Option<u32>,
+
+    #[serde(flatten)]
+    pub paging: PageParams,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -86,6 +138,9 @@ pub struct ListPullsParams {
     #[schemars(description = "Maximum number of results")]
     #[serde(default)]
     pub per_page: Option<u32>,
+
+    #[serde(flatten)]
+    pub paging: PageParams,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -101,6 +156,44 @@ pub struct PullParams {
     pub pr_number: u64,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PullFilesParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Pull request number")]
+    pub pr_number: u64,
+
+    #[schemars(description = "Only include files whose path matches this glob (e.g. 'src/**/*.rs')")]
+    #[serde(default)]
+    pub path: Option<String>,
+
+    #[schemars(description = "Cap on total patch bytes returned across all files (default: 100000)")]
+    #[serde(default)]
+    pub max_patch_bytes: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PullReviewCommentsParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Pull request number")]
+    pub pr_number: u64,
+
+    #[schemars(description = "Only include comments on files whose path matches this glob (e.g. 'src/**/*.rs')")]
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct SearchCodeParams {
     #[schemars(description = "Search query (GitHub code search syntax)")]
@@ -119,6 +212,112 @@ pub struct SearchCodeParams {
     pub per_page: Option<u32>,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SearchIssuesParams {
+    #[schemars(description = "Search query (GitHub issue search syntax, e.g. 'timeout is:open label:bug assignee:@me')")]
+    pub query: String,
+
+    #[schemars(description = "Scope search to this owner/org")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Scope search to this repository")]
+    #[serde(default)]
+    pub repo: Option<String>,
+
+    #[schemars(description = "Only return this kind of result: issue or pr")]
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>,
+
+    #[schemars(description = "Sort by: comments, reactions, interactions, created, updated (default: best match)")]
+    #[serde(default)]
+    pub sort: Option<String>,
+
+    #[schemars(description = "Sort order: asc or desc (default: desc)")]
+    #[serde(default)]
+    pub order: Option<String>,
+
+    #[schemars(description = "Maximum number of results")]
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SearchReposParams {
+    #[schemars(description = "Search query (GitHub repository search syntax, e.g. 'payments in:name,description language:go')")]
+    pub query: String,
+
+    #[schemars(description = "Scope search to this owner/org")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Sort by: stars, forks, help-wanted-issues, updated (default: best match)")]
+    #[serde(default)]
+    pub sort: Option<String>,
+
+    #[schemars(description = "Sort order: asc or desc (default: desc)")]
+    #[serde(default)]
+    pub order: Option<String>,
+
+    #[schemars(description = "Maximum number of results")]
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SearchCommitsParams {
+    #[schemars(description = "Search query (GitHub commit search syntax, e.g. 'fix timeout author:octocat')")]
+    pub query: String,
+
+    #[schemars(description = "Scope search to this owner/org")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Scope search to this repository")]
+    #[serde(default)]
+    pub repo: Option<String>,
+
+    #[schemars(description = "Sort by: author-date, committer-date (default: best match)")]
+    #[serde(default)]
+    pub sort: Option<String>,
+
+    #[schemars(description = "Sort order: asc or desc (default: desc)")]
+    #[serde(default)]
+    pub order: Option<String>,
+
+    #[schemars(description = "Maximum number of results")]
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SearchUsersParams {
+    #[schemars(description = "Search query (GitHub user search syntax, e.g. 'jane type:user location:berlin')")]
+    pub query: String,
+
+    #[schemars(description = "Sort by: followers, repositories, joined (default: best match)")]
+    #[serde(default)]
+    pub sort: Option<String>,
+
+    #[schemars(description = "Sort order: asc or desc (default: desc)")]
+    #[serde(default)]
+    pub order: Option<String>,
+
+    #[schemars(description = "Maximum number of results")]
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GraphqlParams {
+    #[schemars(description = "GraphQL query document. Mutations are rejected unless the server allows writes")]
+    pub query: String,
+
+    #[schemars(description = "Variables for the query, as a JSON object")]
+    #[serde(default)]
+    pub variables: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct ActionsParams {
     #[schemars(description = "Repository owner (user or org)")]
@@ -137,6 +336,52 @@ pub struct ActionsParams {
     pub per_page: Option<u32>,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RunJobsParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Workflow run ID (from list_actions_runs)")]
+    pub run_id: u64,
+
+    #[schemars(description = "Include jobs from all attempts instead of only the latest (default: false)")]
+    #[serde(default)]
+    pub all_attempts: Option<bool>,
+
+    #[schemars(description = "Maximum number of results")]
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct JobLogsParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Job ID (from list_run_jobs)")]
+    pub job_id: u64,
+
+    #[schemars(description = "Step number to extract. Defaults to the first failed step, or the whole log if no step failed")]
+    #[serde(default)]
+    pub step: Option<u64>,
+
+    #[schemars(description = "1-based line to start the window at. If omitted, the last max_lines lines are returned")]
+    #[serde(default)]
+    pub start_line: Option<usize>,
+
+    #[schemars(description = "Maximum number of log lines to return (default: 200, max 5000)")]
+    #[serde(default)]
+    pub max_lines: Option<usize>,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct ListCommitsParams {
     #[schemars(description = "Repository owner (user or org)")]
@@ -154,9 +399,28 @@ pub struct ListCommitsParams {
     #[serde(default)]
     pub author: Option<String>,
 
+    #[schemars(description = "Filter commits by committer (GitHub username or email)")]
+    #[serde(default)]
+    pub committer: Option<String>,
+
+    #[schemars(description = "Only commits touching this file or directory (e.g. 'src/auth')")]
+    #[serde(default)]
+    pub path: Option<String>,
+
+    #[schemars(description = "Only commits after this time (ISO 8601, e.g. '2024-05-01' or '2024-05-01T12:00:00Z')")]
+    #[serde(default)]
+    pub since: Option<String>,
+
+    #[schemars(description = "Only commits before this time (ISO 8601)")]
+    #[serde(default)]
+    pub until: Option<String>,
+
     #[schemars(description = "Maximum number of results")]
     #[serde(default)]
     pub per_page: Option<u32>,
+
+    #[serde(flatten)]
+    pub paging: PageParams,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -173,6 +437,49 @@ pub struct CommitRefParams {
     pub sha: String,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ChecksParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Commit SHA, branch name, or tag to check (give this or pr_number)")]
+    #[serde(default, rename = "ref")]
+    pub git_ref: Option<String>,
+
+    #[schemars(description = "Pull request number; its head commit is checked (give this or ref)")]
+    #[serde(default)]
+    pub pr_number: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CompareParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Base branch, tag, or SHA. May also be 'base...head', in which case head is omitted")]
+    pub base: String,
+
+    #[schemars(description = "Head branch, tag, or SHA. Use 'owner:branch' for a branch in a fork")]
+    #[serde(default)]
+    pub head: Option<String>,
+
+    #[schemars(description = "Include each file's patch (default: false)")]
+    #[serde(default)]
+    pub include_patch: Option<bool>,
+
+    #[schemars(description = "Cap on total patch bytes returned across all files (default: 100000)")]
+    #[serde(default)]
+    pub max_patch_bytes: Option<usize>,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct RepoPageParams {
     #[schemars(description = "Repository owner (user or org)")]
@@ -185,6 +492,9 @@ pub struct RepoPageParams {
     #[schemars(description = "Maximum number of results")]
     #[serde(default)]
     pub per_page: Option<u32>,
+
+    #[serde(flatten)]
+    pub paging: PageParams,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -204,48 +514,1336 @@ pub struct FileContentsParams {
     pub git_ref: Option<String>,
 }
 
-impl McpGithubServer {
-    pub fn new(
-        github: octocrab::Octocrab,
-        default_owner: Option<String>,
-        max_results: u32,
-    ) -> Self {
-        Self {
-            github: Arc::new(github),
-            default_owner,
-            max_results,
-            tool_router: Self::tool_router(),
-        }
-    }
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct BlameParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
 
-    fn resolve_owner(&self, param: Option<&str>) -> Result<String, McpGithubError> {
-        param
-            .map(String::from)
-            .or_else(|| self.default_owner.clone())
-            .ok_or_else(|| {
-                McpGithubError::MissingParam(
-                    "owner is required (or set --owner default)".to_string(),
-                )
-            })
-    }
+    #[schemars(description = "Repository name")]
+    pub repo: String,
 
-    /// Cap per_page to 100 (GitHub API maximum) and safely cast to u8.
-    fn capped_per_page(&self, per_page: Option<u32>) -> u8 {
-        std::cmp::min(per_page.unwrap_or(self.max_results), 100) as u8
-    }
+    #[schemars(description = "File path within the repository")]
+    pub path: String,
 
-    fn err(&self, e: McpGithubError) -> ErrorData {
-        e.to_mcp_error()
-    }
+    #[schemars(description = "Git ref (branch, tag, or SHA). Defaults to HEAD")]
+    #[serde(default, rename = "ref")]
+    pub git_ref: Option<String>,
+
+    #[schemars(description = "First line to attribute (1-based, default: 1)")]
+    #[serde(default)]
+    pub start_line: Option<u64>,
+
+    #[schemars(description = "Last line to attribute (default: end of file)")]
+    #[serde(default)]
+    pub end_line: Option<u64>,
 }
 
-/// Format an issue/PR state as a lowercase string.
-fn format_state(state: &octocrab::models::IssueState) -> &'static str {
-    match state {
-        octocrab::models::IssueState::Open => "open",
-        octocrab::models::IssueState::Closed => "closed",
-        _ => "unknown",
-    }
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListDirectoryParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Directory path within the repository (default: repository root)")]
+    #[serde(default)]
+    pub path: Option<String>,
+
+    #[schemars(description = "Git ref (branch, tag, or SHA). Defaults to the repo's default branch")]
+    #[serde(default, rename = "ref")]
+    pub git_ref: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct TreeParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Git ref (branch, tag, or SHA). Defaults to the repo's default branch")]
+    #[serde(default, rename = "ref")]
+    pub git_ref: Option<String>,
+
+    #[schemars(description = "Only include entries under this directory (e.g. 'src/server')")]
+    #[serde(default)]
+    pub path: Option<String>,
+
+    #[schemars(description = "Only include entries whose full path matches this glob (e.g. '**/*.rs')")]
+    #[serde(default)]
+    pub pattern: Option<String>,
+
+    #[schemars(description = "Walk subdirectories (default: true)")]
+    #[serde(default)]
+    pub recursive: Option<bool>,
+
+    #[schemars(description = "Maximum number of entries to return (default: 1000)")]
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CreateIssueParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Issue title")]
+    pub title: String,
+
+    #[schemars(description = "Issue body (Markdown)")]
+    #[serde(default)]
+    pub body: Option<String>,
+
+    #[schemars(description = "Usernames to assign")]
+    #[serde(default)]
+    pub assignees: Option<Vec<String>>,
+
+    #[schemars(description = "Label names to apply")]
+    #[serde(default)]
+    pub labels: Option<Vec<String>>,
+
+    #[schemars(description = "Milestone number")]
+    #[serde(default)]
+    pub milestone: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct UpdateIssueParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Issue number")]
+    pub issue_number: u64,
+
+    #[schemars(description = "New issue title")]
+    #[serde(default)]
+    pub title: Option<String>,
+
+    #[schemars(description = "New issue body (Markdown)")]
+    #[serde(default)]
+    pub body: Option<String>,
+
+    #[schemars(description = "Set state: open or closed")]
+    #[serde(default)]
+    pub state: Option<String>,
+
+    #[schemars(
+        description = "Reason for the state change: completed, not_planned, duplicate, or reopened"
+    )]
+    #[serde(default)]
+    pub state_reason: Option<String>,
+
+    #[schemars(description = "Replace assignees with these usernames (empty list clears)")]
+    #[serde(default)]
+    pub assignees: Option<Vec<String>>,
+
+    #[schemars(description = "Replace labels with these names (empty list clears)")]
+    #[serde(default)]
+    pub labels: Option<Vec<String>>,
+
+    #[schemars(description = "Milestone number")]
+    #[serde(default)]
+    pub milestone: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct IssueCommentParams {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Issue or pull request number")]
+    pub issue_number: u64,
+
+    #[schemars(description = "Comment body (Markdown)")]
+    pub body: String,
+}
+
+// -- Prompt argument types --
+//
+// Prompt arguments arrive as strings, so numeric arguments also accept "42".
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PullPromptArgs {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Pull request number")]
+    #[serde(deserialize_with = "u64_from_arg")]
+    pub number: u64,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct IssuePromptArgs {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Issue number")]
+    #[serde(deserialize_with = "u64_from_arg")]
+    pub number: u64,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CiFailurePromptArgs {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Workflow run ID. Defaults to the most recent failed run")]
+    #[serde(default, deserialize_with = "opt_u64_from_arg")]
+    pub number: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ReleaseNotesPromptArgs {
+    #[schemars(description = "Repository owner (user or org)")]
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    #[schemars(description = "Repository name")]
+    pub repo: String,
+
+    #[schemars(description = "Branch to release from (default: the repository's default branch)")]
+    #[serde(default)]
+    pub sha: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberArg {
+    Number(u64),
+    Text(String),
+}
+
+impl NumberArg {
+    fn parse<E: serde::de::Error>(self) -> Result<u64, E> {
+        match self {
+            NumberArg::Number(n) => Ok(n),
+            NumberArg::Text(s) => s
+                .trim()
+                .trim_start_matches('#')
+                .parse()
+                .map_err(|_| E::custom(format!("expected a number, got '{}'", s))),
+        }
+    }
+}
+
+fn u64_from_arg<'de, D: serde::Deserializer<'de>>(d: D) -> Result<u64, D::Error> {
+    NumberArg::deserialize(d)?.parse()
+}
+
+fn opt_u64_from_arg<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+    match Option::<NumberArg>::deserialize(d)? {
+        Some(NumberArg::Text(s)) if s.trim().is_empty() => Ok(None),
+        Some(arg) => arg.parse().map(Some),
+        None => Ok(None),
+    }
+}
+
+impl McpGithubServer {
+    pub fn new(
+        github: octocrab::Octocrab,
+        default_owner: Option<String>,
+        max_results: u32,
+    ) -> Self {
+        Self {
+            github: Arc::new(github),
+            graphql_url: "/graphql".to_string(),
+            cache: Arc::new(ResponseCache::new(std::time::Duration::ZERO, None)),
+            default_owner,
+            max_results,
+            allow_writes: false,
+            resource_repo: None,
+            repo_policy: Arc::new(RepoPolicy::default()),
+            error_results: false,
+            toolsets: Toolset::ALL.to_vec(),
+            disabled_tools: Vec::new(),
+            tool_router: Self::tool_router(),
+            prompt_router: Self::prompt_router(),
+        }
+    }
+
+    /// Send GraphQL queries to `url` rather than `/graphql` under the API
+    /// root. GitHub Enterprise Server serves GraphQL outside `/api/v3`.
+    pub fn with_graphql_url(mut self, url: String) -> Self {
+        self.graphql_url = url;
+        self
+    }
+
+    /// Replace the default response cache (in-memory, revalidate on every call).
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Arc::new(cache);
+        self
+    }
+
+    /// Response cache counters, for diagnostics.
+    pub fn cache(&self) -> &ResponseCache {
+        &self.cache
+    }
+
+    /// Register the tools that modify GitHub state. Without this the server
+    /// is read-only and the write tools are not advertised to clients.
+    pub fn with_writes(mut self, allow: bool) -> Self {
+        if allow && !self.allow_writes {
+            self.tool_router += Self::issue_write_router();
+            self.prune_tools();
+        }
+        self.allow_writes = allow;
+        self
+    }
+
+    /// Only advertise tools from `toolsets` (plus those outside any
+    /// toolset, like `get_rate_limit`), minus the tools named in `disabled`.
+    pub fn with_toolsets(mut self, toolsets: Vec<Toolset>, disabled: Vec<String>) -> Self {
+        self.toolsets = toolsets;
+        self.disabled_tools = disabled;
+        self.prune_tools();
+        self
+    }
+
+    fn tool_enabled(&self, name: &str) -> bool {
+        if self.disabled_tools.iter().any(|d| d == name) {
+            return false;
+        }
+        match toolsets::toolset_of(name) {
+            Some(Some(toolset)) => self.toolsets.contains(&toolset),
+            _ => true,
+        }
+    }
+
+    fn prune_tools(&mut self) {
+        let names: Vec<String> = self
+            .tool_router
+            .list_all()
+            .into_iter()
+            .map(|t| t.name.to_string())
+            .collect();
+        for name in names {
+            if !self.tool_enabled(&name) {
+                self.tool_router.remove_route(&name);
+            }
+        }
+        // Prompts gather their data through tools; drop those missing one
+        let prompts: Vec<String> = self
+            .prompt_router
+            .list_all()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        for name in prompts {
+            let tools = toolsets::prompt_tools(&name);
+            if !tools.iter().all(|t| self.tool_router.has_route(t)) {
+                self.prompt_router.remove_route(&name);
+            }
+        }
+    }
+
+    /// Enumerate this repository's files in `resources/list`. Files of any
+    /// repository can still be read through the resource URI template.
+    pub fn with_resource_repo(mut self, owner: String, repo: String) -> Self {
+        self.resource_repo = Some((owner, repo));
+        self
+    }
+
+    /// Restrict every tool, search, and resource to the repositories the
+    /// policy allows.
+    pub fn with_repo_policy(mut self, policy: RepoPolicy) -> Self {
+        self.repo_policy = Arc::new(policy);
+        self
+    }
+
+    /// Report recoverable failures (missing issues, permissions, rate
+    /// limits, ...) as `isError` tool results with a JSON body the model can
+    /// read, instead of protocol errors. Malformed arguments still fail the
+    /// call.
+    pub fn with_error_results(mut self, enabled: bool) -> Self {
+        self.error_results = enabled;
+        self
+    }
+
+    fn resolve_owner(&self, param: Option<&str>) -> Result<String, McpGithubError> {
+        param
+            .map(String::from)
+            .or_else(|| self.default_owner.clone())
+            .ok_or_else(|| {
+                McpGithubError::MissingParam(
+                    "owner is required (or set --owner default)".to_string(),
+                )
+            })
+    }
+
+    /// Resolve the owner of `repo` and check that the repository policy
+    /// allows it. Every tool that addresses a single repository goes
+    /// through here.
+    fn resolve_repo(&self, param: Option<&str>, repo: &str) -> Result<String, McpGithubError> {
+        let owner = self.resolve_owner(param)?;
+        self.repo_policy.check(&owner, repo)?;
+        Ok(owner)
+    }
+
+    /// Drop items whose repository (found by `repo_of`) the policy does
+    /// not allow. Used for results that can span repositories.
+    fn allowed_items<'a, T>(
+        &self,
+        items: impl IntoIterator<Item = &'a T>,
+        repo_of: impl Fn(&T) -> Option<String>,
+    ) -> Vec<&'a T>
+    where
+        T: 'a,
+    {
+        items
+            .into_iter()
+            .filter(|item| {
+                !self.repo_policy.is_restricted()
+                    || repo_of(item).is_some_and(|r| self.repo_policy.allows(&r))
+            })
+            .collect()
+    }
+
+    /// Append `repo:owner/repo` or `org:owner` to a search query, using the
+    /// default owner when none is given. Under a repository policy, the
+    /// scope and any `repo:`, `org:`, or `user:` qualifiers in the query
+    /// must be allowed; results are filtered by the caller.
+    fn scoped_query(
+        &self,
+        query: &str,
+        owner: Option<&String>,
+        repo: Option<&String>,
+    ) -> Result<String, McpGithubError> {
+        if self.repo_policy.is_restricted() {
+            for (key, value) in search_qualifiers(query) {
+                match key.as_str() {
+                    "repo" => match value.split_once('/') {
+                        Some((owner, repo)) => self.repo_policy.check(owner, repo)?,
+                        None => {
+                            return Err(McpGithubError::MissingParam(format!(
+                                "repo:{} must be repo:OWNER/REPO",
+                                value
+                            )))
+                        }
+                    },
+                    "org" | "user" => self.repo_policy.check_owner(value)?,
+                    _ => {}
+                }
+            }
+        }
+        Ok(match (owner.or(self.default_owner.as_ref()), repo) {
+            (Some(owner), Some(repo)) => {
+                self.repo_policy.check(owner, repo)?;
+                format!("{} repo:{}/{}", query, owner, repo)
+            }
+            (Some(owner), None) => {
+                self.repo_policy.check_owner(owner)?;
+                format!("{} org:{}", query, owner)
+            }
+            (None, _) => query.to_string(),
+        })
+    }
+
+    /// Format search results. Under a repository policy GitHub's
+    /// `total_count` would also count matches in hidden repositories, so it
+    /// is left out.
+    fn search_result(
+        &self,
+        query: &str,
+        items: Vec<serde_json::Value>,
+        response: &serde_json::Value,
+    ) -> CallToolResult {
+        let mut body = serde_json::json!({
+            "query": query,
+            "count": items.len(),
+            "results": items,
+            "incomplete_results": response.get("incomplete_results"),
+        });
+        if !self.repo_policy.is_restricted() {
+            body["total_count"] = response.get("total_count").cloned().unwrap_or_default();
+        }
+        let text = serde_json::to_string_pretty(&body).unwrap_or_else(|_| "{}".to_string());
+        CallToolResult::success(vec![Content::text(text)])
+    }
+
+    /// Run a query against `/search/{kind}`. The query is sent as a URL
+    /// parameter, so GitHub qualifiers need no escaping by the caller.
+    async fn search(
+        &self,
+        kind: &str,
+        query: &str,
+        sort: Option<&str>,
+        order: Option<&str>,
+        per_page: Option<u32>,
+    ) -> Result<serde_json::Value, McpGithubError> {
+        check_choice(order, "order", &["asc", "desc"])?;
+        let per_page = self.capped_per_page(per_page).to_string();
+        let mut query_params = vec![("q", query), ("per_page", per_page.as_str())];
+        if let Some(sort) = sort {
+            query_params.push(("sort", sort));
+        }
+        if let Some(order) = order {
+            query_params.push(("order", order));
+        }
+
+        let route = format!("/search/{}", kind);
+        let (route, query_params) = (&route, &query_params);
+        self.call_github("search", || self.github.get(route, Some(query_params)))
+            .await
+    }
+
+    /// Cap per_page to 100 (GitHub API maximum) and safely cast to u8.
+    fn capped_per_page(&self, per_page: Option<u32>) -> u8 {
+        std::cmp::min(per_page.unwrap_or(self.max_results), 100) as u8
+    }
+
+    /// Resolve the pagination params of a list tool. An explicit `limit`
+    /// doubles as the page size hint so large limits fetch full pages.
+    fn page_request(
+        &self,
+        per_page: Option<u32>,
+        paging: &PageParams,
+    ) -> Result<PageRequest, McpGithubError> {
+        let per_page = self.capped_per_page(per_page.or(paging.limit));
+        PageRequest::from_params(
+            per_page,
+            paging.page,
+            paging.cursor.as_deref(),
+            paging.limit,
+        )
+    }
+
+    /// GET a raw API route through the response cache, retrying on
+    /// secondary rate limits.
+    async fn api_get<R: octocrab::FromResponse>(&self, route: &str) -> Result<R, McpGithubError> {
+        ratelimit::with_retry(|| self.cache.get(&self.github, route)).await
+    }
+
+    /// Send a write to a raw API route, retrying on secondary rate limits.
+    /// Failures keep their response headers, so missing OAuth scopes are
+    /// named (typed octocrab calls drop them).
+    async fn api_send<R: octocrab::FromResponse>(
+        &self,
+        method: http::Method,
+        route: &str,
+        body: &impl Serialize,
+    ) -> Result<R, McpGithubError> {
+        ratelimit::with_retry(|| async {
+            let response = if method == http::Method::PATCH {
+                self.github._patch(route, Some(body)).await?
+            } else {
+                self.github._post(route, Some(body)).await?
+            };
+            let status = response.status();
+            if !status.is_success() {
+                return Err(cache::failure(route, status, response).await);
+            }
+            // Cached reads of what was just changed are now stale
+            self.cache
+                .invalidate_prefix(&written_collection(route))
+                .await;
+            Ok(R::from_response(response).await?)
+        })
+        .await
+    }
+
+    /// Download a job log. The logs route redirects to a short-lived
+    /// plain-text download; only the last `max_bytes` are kept, and the
+    /// flag tells whether earlier output was dropped.
+    async fn download_log(
+        &self,
+        route: &str,
+        max_bytes: usize,
+    ) -> Result<(String, bool), McpGithubError> {
+        let mut response = self.github._get(route).await?;
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(http::header::LOCATION)
+                .and_then(|l| l.to_str().ok())
+                .map(String::from);
+            if let Some(location) = location {
+                response = self.github._get(location).await?;
+            }
+        }
+        let status = response.status();
+        if !status.is_success() {
+            return Err(cache::failure(route, status, response).await);
+        }
+
+        let mut body = response.into_body();
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut truncated = false;
+        while let Some(frame) = body.frame().await {
+            if let Ok(data) = frame?.into_data() {
+                bytes.extend_from_slice(&data);
+                if bytes.len() > 2 * max_bytes {
+                    bytes.drain(..bytes.len() - max_bytes);
+                    truncated = true;
+                }
+            }
+        }
+        if bytes.len() > max_bytes {
+            bytes.drain(..bytes.len() - max_bytes);
+            truncated = true;
+        }
+        let mut log = String::from_utf8_lossy(&bytes).into_owned();
+        if truncated {
+            // Drop the partial line the cut left behind
+            let start = log.find('\n').map_or(0, |i| i + 1);
+            log.drain(..start);
+        }
+        Ok((log, truncated))
+    }
+
+    /// Fetch a list route 100 items at a time until a short page or
+    /// `max_pages` pages have been read.
+    async fn api_get_all(
+        &self,
+        route: &str,
+        max_pages: u32,
+    ) -> Result<Vec<serde_json::Value>, McpGithubError> {
+        let mut items = Vec::new();
+        for page in 1..=max_pages {
+            let batch: Vec<serde_json::Value> = self
+                .api_get(&format!("{}?per_page=100&page={}", route, page))
+                .await?;
+            let done = batch.len() < 100;
+            items.extend(batch);
+            if done {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// Like [`Self::api_get_all`] for routes that wrap their items in an
+    /// object under `key` (e.g. `check_runs`). Returns the first page with
+    /// every item gathered under `key`, and whether `max_pages` full pages
+    /// were read, so items may be missing.
+    async fn api_get_all_wrapped(
+        &self,
+        route: &str,
+        key: &str,
+        max_pages: u32,
+    ) -> Result<(serde_json::Value, bool), McpGithubError> {
+        let mut first: Option<serde_json::Value> = None;
+        let mut items = Vec::new();
+        let mut incomplete = false;
+        for page in 1..=max_pages {
+            let mut response: serde_json::Value = self
+                .api_get(&format!("{}?per_page=100&page={}", route, page))
+                .await?;
+            let batch = match response.get_mut(key).map(serde_json::Value::take) {
+                Some(serde_json::Value::Array(batch)) => batch,
+                _ => Vec::new(),
+            };
+            let done = batch.len() < 100;
+            items.extend(batch);
+            first.get_or_insert(response);
+            if done {
+                break;
+            }
+            incomplete = page == max_pages;
+        }
+        let mut first = first.unwrap_or_default();
+        first[key] = serde_json::Value::Array(items);
+        Ok((first, incomplete))
+    }
+
+    /// Send a GraphQL query and return the whole response. GraphQL reports
+    /// most failures in an `errors` array alongside a 200 status; those are
+    /// raised only when no `data` came back, so partial results survive.
+    async fn graphql_response(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<serde_json::Value, McpGithubError> {
+        let payload = serde_json::json!({ "query": query, "variables": variables });
+        let (url, payload) = (&self.graphql_url, &payload);
+        let response: serde_json::Value = self
+            .call_github("GraphQL query", || self.github.post(url, Some(payload)))
+            .await?;
+        if response.get("data").map_or(true, |d| d.is_null()) {
+            if let Some(errors) = graphql_errors(&response) {
+                return Err(self.graphql_error(errors).await);
+            }
+        }
+        Ok(response)
+    }
+
+    /// Run a GraphQL query and return its `data`, failing on any error.
+    async fn graphql(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<serde_json::Value, McpGithubError> {
+        let response = self.graphql_response(query, variables).await?;
+        if let Some(errors) = graphql_errors(&response) {
+            return Err(self.graphql_error(errors).await);
+        }
+        Ok(response.get("data").cloned().unwrap_or_default())
+    }
+
+    /// Map GraphQL `errors` by their `type`: rate limiting and missing
+    /// objects get their dedicated variants, anything else is reported as is.
+    async fn graphql_error(&self, errors: &[serde_json::Value]) -> McpGithubError {
+        let kind = |e: &serde_json::Value| e.get("type").and_then(|t| t.as_str()).map(String::from);
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter_map(|e| e.get("message")?.as_str())
+            .collect();
+        let message = messages.join("; ");
+        if errors
+            .iter()
+            .any(|e| kind(e).as_deref() == Some("RATE_LIMITED"))
+        {
+            if let Some(reset_at) = self.primary_reset().await {
+                return McpGithubError::RateLimited { reset_at };
+            }
+        }
+        if errors
+            .iter()
+            .all(|e| kind(e).as_deref() == Some("NOT_FOUND"))
+        {
+            return McpGithubError::NotFound(message);
+        }
+        McpGithubError::GraphQL(message)
+    }
+
+    /// Resolution state of each review comment on a pull request, keyed by
+    /// comment ID. Only GraphQL exposes review threads.
+    async fn review_thread_states(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u64,
+    ) -> Result<HashMap<u64, ThreadState>, McpGithubError> {
+        let mut states = HashMap::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_REVIEW_THREAD_PAGES {
+            let data = self
+                .graphql(
+                    REVIEW_THREADS_QUERY,
+                    serde_json::json!({
+                        "owner": owner,
+                        "repo": repo,
+                        "number": pr_number,
+                        "cursor": cursor,
+                    }),
+                )
+                .await?;
+            let threads = data
+                .pointer("/repository/pullRequest/reviewThreads")
+                .ok_or_else(|| {
+                    McpGithubError::NotFound(format!(
+                        "pull request {}/{}#{}",
+                        owner, repo, pr_number
+                    ))
+                })?;
+            for thread in threads
+                .get("nodes")
+                .and_then(|n| n.as_array())
+                .into_iter()
+                .flatten()
+            {
+                let state = ThreadState {
+                    resolved: thread.get("isResolved").and_then(|v| v.as_bool()),
+                    outdated: thread.get("isOutdated").and_then(|v| v.as_bool()),
+                };
+                let ids = thread
+                    .pointer("/comments/nodes")
+                    .and_then(|n| n.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|c| c.get("databaseId")?.as_u64());
+                for id in ids {
+                    states.insert(id, state);
+                }
+            }
+            if threads
+                .pointer("/pageInfo/hasNextPage")
+                .and_then(|v| v.as_bool())
+                != Some(true)
+            {
+                break;
+            }
+            cursor = threads
+                .pointer("/pageInfo/endCursor")
+                .and_then(|v| v.as_str())
+                .map(String::from);
+        }
+        Ok(states)
+    }
+
+    /// Run a typed octocrab call, retrying on secondary rate limits. `call`
+    /// is invoked once per attempt, so it must build its request afresh.
+    async fn call_github<T, F, Fut>(&self, target: &str, mut call: F) -> Result<T, McpGithubError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = octocrab::Result<T>>,
+    {
+        ratelimit::with_retry(|| {
+            let attempt = call();
+            async move {
+                match attempt.await {
+                    Ok(value) => Ok(value),
+                    Err(e) => Err(self.github_error(e, target).await),
+                }
+            }
+        })
+        .await
+    }
+
+    /// Convert an error from a typed octocrab call; `target` names what was
+    /// requested. Typed calls don't expose response headers, so an exhausted
+    /// primary limit is confirmed against `/rate_limit` (which does not
+    /// count towards the limit) to get its reset time, and missing OAuth
+    /// scopes cannot be named.
+    async fn github_error(&self, e: octocrab::Error, target: &str) -> McpGithubError {
+        match ratelimit::classify_error(&e) {
+            Some(Throttle::Secondary) => McpGithubError::SecondaryRateLimited { retry_after: None },
+            Some(Throttle::Primary) => match self.primary_reset().await {
+                Some(reset_at) => McpGithubError::RateLimited { reset_at },
+                None => McpGithubError::from_github(e, None, target),
+            },
+            None => McpGithubError::from_github(e, None, target),
+        }
+    }
+
+    /// When the exhausted rate-limit resource resets, from `/rate_limit`.
+    async fn primary_reset(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.github
+            .get::<serde_json::Value, _, ()>("/rate_limit", None)
+            .await
+            .ok()
+            .and_then(|body| ratelimit::exhausted_reset(&body))
+    }
+
+    /// Gather list results from `first` onwards, following `rel=next` links
+    /// through [`Self::api_get`].
+    async fn collect_pages<T: DeserializeOwned>(
+        &self,
+        paging: &PageRequest,
+        first: octocrab::Page<T>,
+    ) -> Result<(Vec<T>, Option<String>), McpGithubError> {
+        pagination::collect_pages(paging, first, |uri| async move { self.api_get(&uri).await })
+            .await
+    }
+
+    /// Fetch a file's metadata and base64 content through the contents API.
+    async fn fetch_file(
+        &self,
+        owner: &str,
+        repo: &str,
+        path: &str,
+        git_ref: Option<&str>,
+    ) -> Result<serde_json::Value, McpGithubError> {
+        sanitize_github_name(owner, "owner")?;
+        sanitize_github_name(repo, "repo")?;
+        sanitize_url_value(path, "path")?;
+        if let Some(git_ref) = git_ref {
+            sanitize_url_value(git_ref, "ref")?;
+        }
+
+        let route = contents_route(owner, repo, path, git_ref);
+        let response: serde_json::Value = self.api_get(&route).await?;
+        if response.is_array() {
+            return Err(McpGithubError::MissingParam(format!(
+                "{} is a directory; use list_directory or get_tree",
+                path
+            )));
+        }
+        Ok(response)
+    }
+
+    /// One page of the resource repository's files, from its default
+    /// branch. The cursor is the offset into the file list.
+    async fn list_repo_resources(
+        &self,
+        cursor: Option<&str>,
+    ) -> Result<ListResourcesResult, McpGithubError> {
+        let Some((ref owner, ref repo)) = self.resource_repo else {
+            return Ok(ListResourcesResult::default());
+        };
+        self.repo_policy.check(owner, repo)?;
+        sanitize_github_name(owner, "owner")?;
+        sanitize_github_name(repo, "repo")?;
+        let offset = cursor
+            .map(|c| c.parse::<usize>())
+            .transpose()
+            .map_err(|_| McpGithubError::MissingParam("cursor is invalid".to_string()))?
+            .unwrap_or(0);
+
+        let info: serde_json::Value = self.api_get(&format!("/repos/{}/{}", owner, repo)).await?;
+        let branch = info
+            .get("default_branch")
+            .and_then(|b| b.as_str())
+            .unwrap_or("HEAD");
+        let route = format!("/repos/{}/{}/git/trees/{}?recursive=1", owner, repo, branch);
+        let tree: serde_json::Value = self.api_get(&route).await?;
+        if tree.get("truncated").and_then(|t| t.as_bool()) == Some(true) {
+            tracing::warn!(
+                owner,
+                repo,
+                "Repository tree truncated; not all files are listed"
+            );
+        }
+
+        let files: Vec<&serde_json::Value> = tree
+            .get("tree")
+            .and_then(|t| t.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|e| e.get("type").and_then(|t| t.as_str()) == Some("blob"))
+            .collect();
+        let resources = files
+            .iter()
+            .skip(offset)
+            .take(RESOURCES_PAGE_SIZE)
+            .filter_map(|e| {
+                let path = e.get("path")?.as_str()?;
+                let uri = BlobUri {
+                    owner: owner.clone(),
+                    repo: repo.clone(),
+                    git_ref: branch.to_string(),
+                    path: path.to_string(),
+                };
+                let mut resource = RawResource::new(uri.to_uri(), path);
+                resource.mime_type = Some(resources::mime_type(path).to_string());
+                resource.size = e.get("size").and_then(|s| s.as_u64()).map(|s| s as u32);
+                Some(resource.no_annotation())
+            })
+            .collect();
+
+        let next = offset + RESOURCES_PAGE_SIZE;
+        Ok(ListResourcesResult {
+            meta: None,
+            next_cursor: (next < files.len()).then(|| next.to_string()),
+            resources,
+        })
+    }
+
+    /// Read a `github://{owner}/{repo}/blob/{ref}/{path}` resource. Text files
+    /// are returned as text, anything else as a base64 blob.
+    async fn read_repo_resource(&self, uri: &str) -> Result<ReadResourceResult, McpGithubError> {
+        let blob = BlobUri::parse(uri)?;
+        self.repo_policy.check(&blob.owner, &blob.repo)?;
+        let response = self
+            .fetch_file(&blob.owner, &blob.repo, &blob.path, Some(&blob.git_ref))
+            .await?;
+        let bytes = decode_content(&response).ok_or_else(|| {
+            McpGithubError::Other(format!(
+                "{} has no inline content (the contents API omits files over 1 MB)",
+                blob.path
+            ))
+        })?;
+
+        let mime_type = resources::mime_type(&blob.path);
+        let contents = match String::from_utf8(bytes) {
+            Ok(text) => ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some(mime_type.to_string()),
+                text,
+                meta: None,
+            },
+            Err(e) => {
+                use base64::Engine;
+                let mime_type = if mime_type.starts_with("text/") {
+                    "application/octet-stream"
+                } else {
+                    mime_type
+                };
+                ResourceContents::BlobResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some(mime_type.to_string()),
+                    blob: base64::engine::general_purpose::STANDARD.encode(e.into_bytes()),
+                    meta: None,
+                }
+            }
+        };
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+
+    fn err(&self, e: McpGithubError) -> ErrorData {
+        e.to_mcp_error()
+    }
+}
+
+/// Turn a failed tool call into an `isError` result when the failure is
+/// recoverable. Handler errors carry their structured body (see
+/// [`McpGithubError::body`]) as data; errors without one, such as argument
+/// deserialization failures, and malformed parameters stay protocol errors.
+fn tool_error_result(e: ErrorData) -> Result<CallToolResult, ErrorData> {
+    let recoverable = e
+        .data
+        .as_ref()
+        .and_then(|d| d.get("code"))
+        .and_then(|c| c.as_str())
+        .is_some_and(|code| code != "invalid_params");
+    if !recoverable {
+        return Err(e);
+    }
+    let body = e.data.unwrap_or_default();
+    let text = serde_json::to_string_pretty(&body).unwrap_or_else(|_| e.message.to_string());
+    Ok(CallToolResult::error(vec![Content::text(text)]))
+}
+
+/// The collection a write to `route` changes, e.g. `/repos/o/r/issues` for
+/// `/repos/o/r/issues/5/comments`. Cache entries below it are invalidated.
+fn written_collection(route: &str) -> String {
+    let path = route.split('?').next().unwrap_or(route);
+    path.split('/').take(5).collect::<Vec<_>>().join("/")
+}
+
+/// Format an issue/PR state as a lowercase string.
+fn format_state(state: &octocrab::models::IssueState) -> &'static str {
+    match state {
+        octocrab::models::IssueState::Open => "open",
+        octocrab::models::IssueState::Closed => "closed",
+        _ => "unknown",
+    }
+}
+
+/// Check that an optional enumerated parameter is one of `allowed`.
+fn check_choice(value: Option<&str>, field: &str, allowed: &[&str]) -> Result<(), McpGithubError> {
+    match value {
+        Some(v) if !allowed.contains(&v) => Err(McpGithubError::MissingParam(format!(
+            "{} must be one of {}, got '{}'",
+            field,
+            allowed.join(", "),
+            v
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// The `items` array of a search response.
+fn search_items(response: &serde_json::Value) -> &[serde_json::Value] {
+    response
+        .get("items")
+        .and_then(|i| i.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// `key:value` qualifiers in a search query, with keys lowercased and
+/// quotes and grouping parentheses stripped. Negated qualifiers (`-repo:`)
+/// only narrow results and are skipped.
+fn search_qualifiers(query: &str) -> Vec<(String, &str)> {
+    query
+        .split_whitespace()
+        .map(|term| term.trim_start_matches('('))
+        .filter(|term| !term.starts_with('-'))
+        .filter_map(|term| term.split_once(':'))
+        .map(|(key, value)| {
+            let value = value.trim_end_matches(')').trim_matches('"');
+            (key.to_ascii_lowercase(), value)
+        })
+        .collect()
+}
+
+/// `owner/repo` from an API URL such as `https://api.github.com/repos/o/r`.
+fn repo_from_api_url(url: &str) -> Option<String> {
+    let (_, rest) = url.rsplit_once("/repos/")?;
+    let mut parts = rest.splitn(3, '/');
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    let repo = parts.next().filter(|s| !s.is_empty())?;
+    Some(format!("{}/{}", owner, repo))
+}
+
+/// Parse an issue state for updates. Only `open` and `closed` are settable.
+fn parse_issue_state(state: &str) -> Result<octocrab::models::IssueState, McpGithubError> {
+    match state {
+        "open" => Ok(octocrab::models::IssueState::Open),
+        "closed" => Ok(octocrab::models::IssueState::Closed),
+        other => Err(McpGithubError::MissingParam(format!(
+            "state must be 'open' or 'closed', got '{}'",
+            other
+        ))),
+    }
+}
+
+/// Parse the reason attached to closing or reopening an issue.
+fn parse_state_reason(
+    reason: &str,
+) -> Result<octocrab::models::issues::IssueStateReason, McpGithubError> {
+    use octocrab::models::issues::IssueStateReason;
+    match reason {
+        "completed" => Ok(IssueStateReason::Completed),
+        "not_planned" => Ok(IssueStateReason::NotPlanned),
+        "duplicate" => Ok(IssueStateReason::Duplicate),
+        "reopened" => Ok(IssueStateReason::Reopened),
+        other => Err(McpGithubError::MissingParam(format!(
+            "state_reason must be one of completed, not_planned, duplicate, reopened; got '{}'",
+            other
+        ))),
+    }
+}
+
+/// Request body for creating or updating an issue. Unset fields are left
+/// out, so an update only changes what was given.
+#[derive(Default, Serialize)]
+struct IssueBody<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<octocrab::models::IssueState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state_reason: Option<octocrab::models::issues::IssueStateReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assignees: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    labels: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    milestone: Option<u64>,
+}
+
+/// Default budget for patch text returned by `list_pull_files`.
+const DEFAULT_MAX_PATCH_BYTES: usize = 100_000;
+
+/// GitHub stops listing pull request files after 3000 entries.
+const MAX_PULL_FILES_PAGES: u32 = 30;
+
+/// Pages of 100 check runs, check suites, or statuses read for a commit.
+const MAX_CHECK_PAGES: u32 = 5;
+
+/// Pages of 100 reviews or review comments read for a pull request.
+const MAX_PULL_REVIEW_PAGES: u32 = 10;
+
+/// Pages of 100 review threads read when looking up resolution state.
+const MAX_REVIEW_THREAD_PAGES: u32 = 10;
+
+const REVIEW_THREADS_QUERY: &str = "\
+query($owner: String!, $repo: String!, $number: Int!, $cursor: String) {
+  repository(owner: $owner, name: $repo) {
+    pullRequest(number: $number) {
+      reviewThreads(first: 100, after: $cursor) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          isResolved
+          isOutdated
+          comments(first: 100) { nodes { databaseId } }
+        }
+      }
+    }
+  }
+}";
+
+const BLAME_QUERY: &str = "\
+query($owner: String!, $repo: String!, $ref: String!, $path: String!) {
+  repository(owner: $owner, name: $repo) {
+    object(expression: $ref) {
+      ... on Commit {
+        oid
+        blame(path: $path) {
+          ranges {
+            startingLine
+            endingLine
+            commit {
+              oid
+              messageHeadline
+              committedDate
+              author { name date user { login } }
+            }
+          }
+        }
+      }
+    }
+  }
+}";
+
+/// Whether the review thread a comment belongs to is resolved or outdated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ThreadState {
+    resolved: Option<bool>,
+    outdated: Option<bool>,
+}
+
+/// Default number of log lines returned by `get_job_logs`.
+const DEFAULT_LOG_LINES: usize = 200;
+
+/// Bytes of a job log read by `get_job_logs`, counted from the end.
+const MAX_LOG_BYTES: usize = 8 * 1024 * 1024;
+
+/// Most log lines `get_job_logs` returns in one call.
+const MAX_LOG_LINES: usize = 5000;
+
+/// Sort keys accepted by the repository search API.
+const SEARCH_REPOS_SORTS: &[&str] = &["stars", "forks", "help-wanted-issues", "updated"];
+
+/// Sort keys accepted by the issue search API.
+const SEARCH_ISSUES_SORTS: &[&str] = &[
+    "comments",
+    "reactions",
+    "reactions-+1",
+    "reactions--1",
+    "reactions-smile",
+    "reactions-thinking_face",
+    "reactions-heart",
+    "reactions-tada",
+    "interactions",
+    "created",
+    "updated",
+];
+
+/// Split the ISO-8601 timestamp GitHub prefixes to every job log line.
+fn split_log_timestamp(line: &str) -> (Option<&str>, &str) {
+    let bytes = line.as_bytes();
+    if bytes.len() > 20 && bytes[4] == b'-' && bytes[10] == b'T' {
+        if let Some((ts, rest)) = line.split_once(' ') {
+            return (Some(ts), rest);
+        }
+    }
+    (None, line)
+}
+
+/// Select the log lines written while a step was running. Job and step
+/// timestamps only have second precision, so compare on the first 19
+/// characters (`YYYY-MM-DDTHH:MM:SS`).
+fn step_log_lines<'a>(log: &'a str, started_at: &str, completed_at: &str) -> Vec<&'a str> {
+    let start = started_at.get(..19).unwrap_or(started_at);
+    let end = completed_at.get(..19).unwrap_or(completed_at);
+    log.lines()
+        .filter_map(|line| {
+            let (ts, rest) = split_log_timestamp(line);
+            let ts = ts?;
+            let ts = ts.get(..19).unwrap_or(ts);
+            (ts >= start && ts <= end).then_some(rest)
+        })
+        .collect()
+}
+
+/// Pick a window of lines: `max_lines` from `start_line` (1-based), or the
+/// last `max_lines` lines when no start is given. Returns the 1-based index
+/// of the first returned line alongside the slice.
+fn line_window<'a, 'b>(
+    lines: &'b [&'a str],
+    start_line: Option<usize>,
+    max_lines: usize,
+) -> (usize, &'b [&'a str]) {
+    let start = match start_line {
+        Some(n) => n.saturating_sub(1).min(lines.len()),
+        None => lines.len().saturating_sub(max_lines),
+    };
+    let end = start.saturating_add(max_lines).min(lines.len());
+    (start + 1, &lines[start..end])
+}
+
+/// Decode the base64 `content` of a contents API response (GitHub wraps it
+/// with embedded newlines).
+fn decode_content(response: &serde_json::Value) -> Option<Vec<u8>> {
+    let content = response.get("content")?.as_str()?;
+    let cleaned: String = content.chars().filter(|ch| !ch.is_whitespace()).collect();
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD
+        .decode(&cleaned)
+        .ok()
+}
+
+/// Route for the contents API, with an optional ref. `path` may be empty for
+/// the repository root.
+fn contents_route(owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> String {
+    let mut route = format!("/repos/{}/{}/contents", owner, repo);
+    if !path.is_empty() {
+        route.push('/');
+        route.push_str(&resources::percent_encode(path, false));
+    }
+    if let Some(git_ref) = git_ref {
+        route.push_str(&format!(
+            "?ref={}",
+            resources::percent_encode(git_ref, true)
+        ));
+    }
+    route
+}
+
+/// Flatten a git tree response into entries, prefixing paths with the
+/// directory the tree was fetched for. Returns the entries and whether
+/// `limit` cut the listing short.
+fn tree_entries(
+    tree: &serde_json::Value,
+    prefix: &str,
+    glob: Option<&globset::GlobMatcher>,
+    limit: usize,
+) -> (Vec<serde_json::Value>, bool) {
+    let mut entries = Vec::new();
+    let items = tree.get("tree").and_then(|t| t.as_array());
+    for item in items.into_iter().flatten() {
+        let Some(rel) = item.get("path").and_then(|p| p.as_str()) else {
+            continue;
+        };
+        let path = if prefix.is_empty() {
+            rel.to_string()
+        } else {
+            format!("{}/{}", prefix, rel)
+        };
+        if glob.is_some_and(|g| !g.is_match(&path)) {
+            continue;
+        }
+        if entries.len() == limit {
+            return (entries, true);
+        }
+        let kind = match item.get("type").and_then(|t| t.as_str()) {
+            Some("blob") => "file",
+            Some("tree") => "dir",
+            Some("commit") => "submodule",
+            _ => "unknown",
+        };
+        entries.push(serde_json::json!({
+            "path": path,
+            "type": kind,
+            "size": item.get("size"),
+            "sha": item.get("sha"),
+        }));
+    }
+    (entries, false)
+}
+
+/// Compile a path glob supplied by a tool caller.
+fn compile_glob(pattern: &str, field: &str) -> Result<globset::GlobMatcher, McpGithubError> {
+    globset::Glob::new(pattern)
+        .map(|g| g.compile_matcher())
+        .map_err(|e| McpGithubError::MissingParam(format!("{} is not a valid glob: {}", field, e)))
+}
+
+/// Truncate `s` to at most `max` bytes without splitting a UTF-8 character.
+fn truncate_utf8(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
 }
 
 /// Validate that a GitHub owner/repo name doesn't contain characters that
@@ -257,876 +1855,4308 @@ fn sanitize_github_name(name: &str, field: &str) -> Result<(), McpGithubError> {
             field
         )));
     }
-    for ch in ['/', '?', '#', '%', '\0', ' ', '\n', '\t'] {
-        if name.contains(ch) {
-            return Err(McpGithubError::MissingParam(format!(
-                "{} contains invalid character '{}'",
-                field, ch
-            )));
+    for ch in ['/', '?', '#', '%', '\0', ' ', '\n', '\t'] {
+        if name.contains(ch) {
+            return Err(McpGithubError::MissingParam(format!(
+                "{} contains invalid character '{}'",
+                field, ch
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Validate a value for use in URL paths or query params. Unlike
+/// `sanitize_github_name`, this allows slashes (for branch names like
+/// `feature/foo` or file paths like `src/main.rs`).
+fn sanitize_url_value(value: &str, field: &str) -> Result<(), McpGithubError> {
+    if value.is_empty() {
+        return Err(McpGithubError::MissingParam(format!(
+            "{} must not be empty",
+            field
+        )));
+    }
+    for ch in ['?', '#', '&', '\0', '\n', '\r', '\t'] {
+        if value.contains(ch) {
+            return Err(McpGithubError::MissingParam(format!(
+                "{} contains invalid character",
+                field
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Normalize an ISO 8601 date or timestamp to the UTC form GitHub expects.
+/// A bare date means midnight UTC.
+fn parse_timestamp(value: &str, field: &str) -> Result<String, McpGithubError> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .or_else(|_| {
+            chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|d| d.and_time(chrono::NaiveTime::MIN).and_utc())
+        })
+        .map_err(|_| {
+            McpGithubError::MissingParam(format!(
+                "{} must be an ISO 8601 date or timestamp, got '{}'",
+                field, value
+            ))
+        })?;
+    Ok(parsed.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+/// Resolve `compare_refs` arguments into a validated (base, head) pair.
+/// Accepts `base...head` in `base` when `head` is omitted, and `owner:ref`
+/// or `owner:repo:ref` for refs in forks.
+fn split_compare_refs(base: &str, head: Option<&str>) -> Result<(String, String), McpGithubError> {
+    let (base, head) = match (base.split_once("..."), head) {
+        (Some((base, head)), None) => (base, head),
+        (None, Some(head)) => (base, head),
+        (Some(_), Some(_)) => {
+            return Err(McpGithubError::MissingParam(
+                "give head either in base as 'base...head' or as its own parameter, not both"
+                    .to_string(),
+            ))
+        }
+        (None, None) => {
+            return Err(McpGithubError::MissingParam(
+                "head is required unless base is written as 'base...head'".to_string(),
+            ))
+        }
+    };
+    for (value, field) in [(base, "base"), (head, "head")] {
+        sanitize_url_value(value, field)?;
+        let mut parts: Vec<&str> = value.split(':').collect();
+        let git_ref = parts.pop().unwrap_or_default();
+        if parts.len() > 2 || git_ref.is_empty() || git_ref.contains("..") {
+            return Err(McpGithubError::MissingParam(format!(
+                "{} must be a ref, 'owner:ref', or 'owner:repo:ref', got '{}'",
+                field, value
+            )));
+        }
+        for name in parts {
+            sanitize_github_name(name, field)?;
+        }
+    }
+    Ok((base.to_string(), head.to_string()))
+}
+
+// -- MCP tool handlers (thin wrappers calling do_* methods) --
+
+#[tool_router]
+impl McpGithubServer {
+    #[tool(
+        name = "list_repos",
+        description = "List repositories for a user or organization"
+    )]
+    async fn list_repos(
+        &self,
+        Parameters(params): Parameters<ListReposParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_owner(params.owner.as_deref())
+            .map_err(|e| self.err(e))?;
+
+        let paging = self
+            .page_request(params.per_page, &params.paging)
+            .map_err(|e| self.err(e))?;
+
+        let owner_ref = &owner;
+        let target = &format!("owner {}", owner);
+        let page = self
+            .call_github(target, || async move {
+                self.github
+                    .orgs(owner_ref)
+                    .list_repos()
+                    .per_page(paging.per_page)
+                    .page(paging.page)
+                    .send()
+                    .await
+            })
+            .await;
+
+        // If org fails, try as user
+        let first = match page {
+            Ok(page) => page,
+            Err(e @ McpGithubError::RateLimited { .. }) => return Err(self.err(e)),
+            Err(_) => self
+                .call_github(target, || async move {
+                    self.github
+                        .users(owner_ref)
+                        .repos()
+                        .per_page(paging.per_page)
+                        .page(paging.page)
+                        .send()
+                        .await
+                })
+                .await
+                .map_err(|e| self.err(e))?,
+        };
+        let (repos, next_cursor) = self
+            .collect_pages(&paging, first)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let results: Vec<serde_json::Value> = self
+            .allowed_items(&repos, |r| r.full_name.clone())
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "name": r.name,
+                    "full_name": r.full_name.as_deref().unwrap_or(""),
+                    "description": r.description.as_deref().unwrap_or(""),
+                    "language": r.language.as_ref().map(|l| l.to_string()).unwrap_or_default(),
+                    "stars": r.stargazers_count.unwrap_or(0),
+                    "forks": r.forks_count.unwrap_or(0),
+                    "private": r.private.unwrap_or(false),
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "owner": owner,
+            "repos": results,
+            "count": results.len(),
+            "next_cursor": next_cursor,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "get_repo",
+        description = "Get repository info including description, stars, forks, language, and default branch"
+    )]
+    async fn get_repo(
+        &self,
+        Parameters(params): Parameters<RepoParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let route = format!("/repos/{}/{}", owner, params.repo);
+        let repo: octocrab::models::Repository =
+            self.api_get(&route).await.map_err(|e| self.err(e))?;
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "name": repo.name,
+            "full_name": repo.full_name,
+            "description": repo.description,
+            "language": repo.language,
+            "default_branch": repo.default_branch,
+            "stars": repo.stargazers_count,
+            "forks": repo.forks_count,
+            "open_issues": repo.open_issues_count,
+            "private": repo.private,
+            "created_at": repo.created_at.map(|t| t.to_string()),
+            "updated_at": repo.updated_at.map(|t| t.to_string()),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "list_issues",
+        description = "List issues in a repository, optionally filtered by state and labels"
+    )]
+    async fn list_issues(
+        &self,
+        Parameters(params): Parameters<ListIssuesParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        let paging = self
+            .page_request(params.per_page, &params.paging)
+            .map_err(|e| self.err(e))?;
+
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let mut route = format!(
+            "/repos/{}/{}/issues?per_page={}&page={}",
+            owner, params.repo, paging.per_page, paging.page
+        );
+        if let Some(state @ ("open" | "closed" | "all")) = params.state.as_deref() {
+            route.push_str(&format!("&state={}", state));
+        }
+        if let Some(ref labels) = params.labels {
+            let labels: Vec<String> = labels
+                .split(',')
+                .map(|l| resources::percent_encode(l.trim(), true))
+                .collect();
+            route.push_str(&format!("&labels={}", labels.join(",")));
+        }
+        let first: octocrab::Page<octocrab::models::issues::Issue> =
+            self.api_get(&route).await.map_err(|e| self.err(e))?;
+        let (issues, next_cursor) = self
+            .collect_pages(&paging, first)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let results: Vec<serde_json::Value> = issues
+            .iter()
+            .map(|i| {
+                let labels: Vec<String> = i.labels.iter().map(|l| l.name.clone()).collect();
+                serde_json::json!({
+                    "number": i.number,
+                    "title": i.title,
+                    "state": format_state(&i.state),
+                    "author": i.user.login,
+                    "labels": labels,
+                    "comments": i.comments,
+                    "created_at": i.created_at.to_string(),
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "issues": results,
+            "count": results.len(),
+            "next_cursor": next_cursor,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "get_issue",
+        description = "Get issue details including body and comments"
+    )]
+    async fn get_issue(
+        &self,
+        Parameters(params): Parameters<IssueParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let route = format!(
+            "/repos/{}/{}/issues/{}",
+            owner, params.repo, params.issue_number
+        );
+        let issue: octocrab::models::issues::Issue =
+            self.api_get(&route).await.map_err(|e| self.err(e))?;
+
+        // Fetch comments
+        let comments: octocrab::Page<octocrab::models::issues::Comment> = self
+            .api_get(&format!("{}/comments", route))
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let comment_items: Vec<serde_json::Value> = comments
+            .items
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "author": c.user.login,
+                    "body": c.body.as_deref().unwrap_or(""),
+                    "created_at": c.created_at.to_string(),
+                })
+            })
+            .collect();
+
+        let labels: Vec<String> = issue.labels.iter().map(|l| l.name.clone()).collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "number": issue.number,
+            "title": issue.title,
+            "state": format_state(&issue.state),
+            "author": issue.user.login,
+            "labels": labels,
+            "body": issue.body.as_deref().unwrap_or(""),
+            "comments": comment_items,
+            "created_at": issue.created_at.to_string(),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "list_pulls",
+        description = "List pull requests in a repository"
+    )]
+    async fn list_pulls(
+        &self,
+        Parameters(params): Parameters<ListPullsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        let paging = self
+            .page_request(params.per_page, &params.paging)
+            .map_err(|e| self.err(e))?;
+
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let mut route = format!(
+            "/repos/{}/{}/pulls?per_page={}&page={}",
+            owner, params.repo, paging.per_page, paging.page
+        );
+        if let Some(state @ ("open" | "closed" | "all")) = params.state.as_deref() {
+            route.push_str(&format!("&state={}", state));
+        }
+        let first: octocrab::Page<octocrab::models::pulls::PullRequest> =
+            self.api_get(&route).await.map_err(|e| self.err(e))?;
+        let (pulls, next_cursor) = self
+            .collect_pages(&paging, first)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let results: Vec<serde_json::Value> = pulls
+            .iter()
+            .map(|p| {
+                serde_json::json!({
+                    "number": p.number,
+                    "title": p.title.as_deref().unwrap_or(""),
+                    "state": p.state.as_ref().map(format_state).unwrap_or("unknown"),
+                    "author": p.user.as_ref().map(|u| u.login.as_str()).unwrap_or("unknown"),
+                    "head": p.head.ref_field,
+                    "base": p.base.ref_field,
+                    "draft": p.draft,
+                    "created_at": p.created_at.map(|t| t.to_string()),
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "pulls": results,
+            "count": results.len(),
+            "next_cursor": next_cursor,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "get_pull",
+        description = "Get pull request details including review summary and changed files count"
+    )]
+    async fn get_pull(
+        &self,
+        Parameters(params): Parameters<PullParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let pr: octocrab::models::pulls::PullRequest = self
+            .api_get(&format!(
+                "/repos/{}/{}/pulls/{}",
+                owner, params.repo, params.pr_number
+            ))
+            .await
+            .map_err(|e| self.err(e))?;
+        let reviews = self
+            .api_get_all(
+                &format!(
+                    "/repos/{}/{}/pulls/{}/reviews",
+                    owner, params.repo, params.pr_number
+                ),
+                MAX_PULL_REVIEW_PAGES,
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+        let requested: Vec<&str> = pr
+            .requested_reviewers
+            .iter()
+            .flatten()
+            .map(|u| u.login.as_str())
+            .chain(pr.requested_teams.iter().flatten().map(|t| t.slug.as_str()))
+            .collect();
+        let mut review_summary = review_summary(&reviews);
+        review_summary["requested"] = serde_json::json!(requested);
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "number": pr.number,
+            "title": pr.title.as_deref().unwrap_or(""),
+            "state": pr.state.as_ref().map(format_state).unwrap_or("unknown"),
+            "author": pr.user.as_ref().map(|u| u.login.as_str()).unwrap_or("unknown"),
+            "body": pr.body.as_deref().unwrap_or(""),
+            "head": pr.head.ref_field,
+            "base": pr.base.ref_field,
+            "draft": pr.draft,
+            "mergeable": pr.mergeable,
+            "additions": pr.additions,
+            "deletions": pr.deletions,
+            "changed_files": pr.changed_files,
+            "commits": pr.commits,
+            "reviews": review_summary,
+            "created_at": pr.created_at.map(|t| t.to_string()),
+            "merged_at": pr.merged_at.map(|t| t.to_string()),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "list_pull_files",
+        description = "List files changed in a pull request with status, patch hunks, and previous filename for renames"
+    )]
+    async fn list_pull_files(
+        &self,
+        Parameters(params): Parameters<PullFilesParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let matcher = params
+            .path
+            .as_deref()
+            .map(|p| compile_glob(p, "path"))
+            .transpose()
+            .map_err(|e| self.err(e))?;
+        let mut budget = params.max_patch_bytes.unwrap_or(DEFAULT_MAX_PATCH_BYTES);
+
+        let route = format!(
+            "/repos/{}/{}/pulls/{}/files",
+            owner, params.repo, params.pr_number
+        );
+        let response = self
+            .api_get_all(&route, MAX_PULL_FILES_PAGES)
+            .await
+            .map_err(|e| self.err(e))?;
+        let total_files = response.len();
+
+        let mut truncated = false;
+        let files: Vec<serde_json::Value> = response
+            .iter()
+            .filter(|f| {
+                let name = f.get("filename").and_then(|n| n.as_str()).unwrap_or("");
+                matcher.as_ref().map_or(true, |m| m.is_match(name))
+            })
+            .map(|f| {
+                // Spend the patch budget in file order; once it runs out the
+                // remaining patches are cut short or dropped.
+                let patch = f.get("patch").and_then(|p| p.as_str()).map(|p| {
+                    let kept = truncate_utf8(p, budget);
+                    budget -= kept.len();
+                    if kept.len() < p.len() {
+                        truncated = true;
+                    }
+                    kept
+                });
+                serde_json::json!({
+                    "filename": f.get("filename"),
+                    "previous_filename": f.get("previous_filename"),
+                    "status": f.get("status"),
+                    "additions": f.get("additions"),
+                    "deletions": f.get("deletions"),
+                    "changes": f.get("changes"),
+                    "patch": patch,
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "pr_number": params.pr_number,
+            "files": files,
+            "count": files.len(),
+            "total_files": total_files,
+            "patch_truncated": truncated,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "list_pull_reviews",
+        description = "List reviews on a pull request with reviewer, state, and body, plus each reviewer's latest verdict"
+    )]
+    async fn list_pull_reviews(
+        &self,
+        Parameters(params): Parameters<PullParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let route = format!(
+            "/repos/{}/{}/pulls/{}/reviews",
+            owner, params.repo, params.pr_number
+        );
+        let response = self
+            .api_get_all(&route, MAX_PULL_REVIEW_PAGES)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let reviews: Vec<serde_json::Value> = response
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "id": r.get("id"),
+                    "reviewer": r.pointer("/user/login"),
+                    "state": r.get("state"),
+                    "submitted_at": r.get("submitted_at"),
+                    "commit_id": r.get("commit_id"),
+                    "body": r.get("body"),
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "pr_number": params.pr_number,
+            "reviews": reviews,
+            "count": reviews.len(),
+            "summary": review_summary(&response),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "list_pull_review_comments",
+        description = "List inline review comments on a pull request with file, line, diff hunk, reply chain, and thread resolution"
+    )]
+    async fn list_pull_review_comments(
+        &self,
+        Parameters(params): Parameters<PullReviewCommentsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let matcher = params
+            .path
+            .as_deref()
+            .map(|p| compile_glob(p, "path"))
+            .transpose()
+            .map_err(|e| self.err(e))?;
+
+        let route = format!(
+            "/repos/{}/{}/pulls/{}/comments",
+            owner, params.repo, params.pr_number
+        );
+        let response = self
+            .api_get_all(&route, MAX_PULL_REVIEW_PAGES)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        // Resolution lives on review threads, which only GraphQL exposes. It
+        // needs a token, so fall back to unknown rather than failing.
+        let states = match self
+            .review_thread_states(&owner, &params.repo, params.pr_number)
+            .await
+        {
+            Ok(states) => Some(states),
+            Err(e) => {
+                tracing::warn!(error = %e, "Could not load review thread state");
+                None
+            }
+        };
+
+        let comments: Vec<serde_json::Value> = response
+            .iter()
+            .filter(|c| {
+                let path = c.get("path").and_then(|p| p.as_str()).unwrap_or("");
+                matcher.as_ref().map_or(true, |m| m.is_match(path))
+            })
+            .map(|c| {
+                let state = c
+                    .get("id")
+                    .and_then(|id| id.as_u64())
+                    .and_then(|id| states.as_ref()?.get(&id).copied());
+                serde_json::json!({
+                    "id": c.get("id"),
+                    "review_id": c.get("pull_request_review_id"),
+                    "author": c.pointer("/user/login"),
+                    "path": c.get("path"),
+                    "line": c.get("line"),
+                    "start_line": c.get("start_line"),
+                    "original_line": c.get("original_line"),
+                    "side": c.get("side"),
+                    "diff_hunk": c.get("diff_hunk"),
+                    "body": c.get("body"),
+                    "in_reply_to": c.get("in_reply_to_id"),
+                    "resolved": state.and_then(|s| s.resolved),
+                    "outdated": state.and_then(|s| s.outdated),
+                    "created_at": c.get("created_at"),
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "pr_number": params.pr_number,
+            "comments": comments,
+            "count": comments.len(),
+            "resolution_known": states.is_some(),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "search_code",
+        description = "Search code across GitHub repositories using GitHub's code search syntax"
+    )]
+    async fn search_code(
+        &self,
+        Parameters(params): Parameters<SearchCodeParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let query = self
+            .scoped_query(&params.query, params.owner.as_ref(), params.repo.as_ref())
+            .map_err(|e| self.err(e))?;
+        let per_page = self.capped_per_page(params.per_page);
+
+        let query = &query;
+        let results = self
+            .call_github("search", || async move {
+                self.github
+                    .search()
+                    .code(query)
+                    .per_page(per_page)
+                    .send()
+                    .await
+            })
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let items: Vec<serde_json::Value> = self
+            .allowed_items(&results.items, |item| item.repository.full_name.clone())
+            .into_iter()
+            .map(|item| {
+                serde_json::json!({
+                    "name": item.name,
+                    "path": item.path,
+                    "repository": item.repository.full_name.as_deref().unwrap_or(""),
+                    "url": item.html_url,
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "query": params.query,
+            "results": items,
+            "count": items.len(),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "search_issues",
+        description = "Search issues and pull requests using GitHub issue search syntax"
+    )]
+    async fn search_issues(
+        &self,
+        Parameters(params): Parameters<SearchIssuesParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        check_choice(params.kind.as_deref(), "type", &["issue", "pr"]).map_err(|e| self.err(e))?;
+        check_choice(params.sort.as_deref(), "sort", SEARCH_ISSUES_SORTS)
+            .map_err(|e| self.err(e))?;
+
+        let mut query = self
+            .scoped_query(&params.query, params.owner.as_ref(), params.repo.as_ref())
+            .map_err(|e| self.err(e))?;
+        match params.kind.as_deref() {
+            Some("issue") => query.push_str(" is:issue"),
+            Some("pr") => query.push_str(" is:pr"),
+            _ => {}
+        }
+
+        let response = self
+            .search(
+                "issues",
+                &query,
+                params.sort.as_deref(),
+                params.order.as_deref(),
+                params.per_page,
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let repo_of = |item: &serde_json::Value| {
+            item.get("repository_url")
+                .and_then(|u| u.as_str())
+                .and_then(repo_from_api_url)
+        };
+        let items: Vec<serde_json::Value> = self
+            .allowed_items(search_items(&response), repo_of)
+            .into_iter()
+            .map(|item| {
+                let labels: Vec<&str> = item
+                    .get("labels")
+                    .and_then(|l| l.as_array())
+                    .map(|l| l.iter().filter_map(|l| l.get("name")?.as_str()).collect())
+                    .unwrap_or_default();
+                let kind = if item.get("pull_request").is_some() {
+                    "pr"
+                } else {
+                    "issue"
+                };
+                serde_json::json!({
+                    "number": item.get("number"),
+                    "repo": repo_of(item),
+                    "title": item.get("title"),
+                    "state": item.get("state"),
+                    "type": kind,
+                    "labels": labels,
+                    "author": item.pointer("/user/login"),
+                    "updated_at": item.get("updated_at"),
+                    "score": item.get("score"),
+                    "url": item.get("html_url"),
+                })
+            })
+            .collect();
+
+        Ok(self.search_result(&query, items, &response))
+    }
+
+    #[tool(
+        name = "search_repositories",
+        description = "Search repositories using GitHub repository search syntax"
+    )]
+    async fn search_repositories(
+        &self,
+        Parameters(params): Parameters<SearchReposParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        check_choice(params.sort.as_deref(), "sort", SEARCH_REPOS_SORTS)
+            .map_err(|e| self.err(e))?;
+
+        let query = self
+            .scoped_query(&params.query, params.owner.as_ref(), None)
+            .map_err(|e| self.err(e))?;
+        let response = self
+            .search(
+                "repositories",
+                &query,
+                params.sort.as_deref(),
+                params.order.as_deref(),
+                params.per_page,
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let full_name = |item: &serde_json::Value| {
+            item.get("full_name")
+                .and_then(|n| n.as_str())
+                .map(String::from)
+        };
+        let items: Vec<serde_json::Value> = self
+            .allowed_items(search_items(&response), full_name)
+            .into_iter()
+            .map(|item| {
+                serde_json::json!({
+                    "full_name": item.get("full_name"),
+                    "description": item.get("description"),
+                    "language": item.get("language"),
+                    "stars": item.get("stargazers_count"),
+                    "forks": item.get("forks_count"),
+                    "open_issues": item.get("open_issues_count"),
+                    "archived": item.get("archived"),
+                    "default_branch": item.get("default_branch"),
+                    "updated_at": item.get("updated_at"),
+                    "score": item.get("score"),
+                    "url": item.get("html_url"),
+                })
+            })
+            .collect();
+
+        Ok(self.search_result(&query, items, &response))
+    }
+
+    #[tool(
+        name = "search_commits",
+        description = "Search commits on default branches using GitHub commit search syntax"
+    )]
+    async fn search_commits(
+        &self,
+        Parameters(params): Parameters<SearchCommitsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        check_choice(
+            params.sort.as_deref(),
+            "sort",
+            &["author-date", "committer-date"],
+        )
+        .map_err(|e| self.err(e))?;
+
+        let query = self
+            .scoped_query(&params.query, params.owner.as_ref(), params.repo.as_ref())
+            .map_err(|e| self.err(e))?;
+        let response = self
+            .search(
+                "commits",
+                &query,
+                params.sort.as_deref(),
+                params.order.as_deref(),
+                params.per_page,
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let repo_of = |item: &serde_json::Value| {
+            item.pointer("/repository/full_name")
+                .and_then(|n| n.as_str())
+                .map(String::from)
+        };
+        let items: Vec<serde_json::Value> = self
+            .allowed_items(search_items(&response), repo_of)
+            .into_iter()
+            .map(|item| {
+                serde_json::json!({
+                    "sha": item.get("sha"),
+                    "repo": item.pointer("/repository/full_name"),
+                    "message": item.pointer("/commit/message"),
+                    "author": item.pointer("/commit/author/name"),
+                    "author_login": item.pointer("/author/login"),
+                    "date": item.pointer("/commit/author/date"),
+                    "score": item.get("score"),
+                    "url": item.get("html_url"),
+                })
+            })
+            .collect();
+
+        Ok(self.search_result(&query, items, &response))
+    }
+
+    #[tool(
+        name = "search_users",
+        description = "Search users and organizations using GitHub user search syntax"
+    )]
+    async fn search_users(
+        &self,
+        Parameters(params): Parameters<SearchUsersParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        check_choice(
+            params.sort.as_deref(),
+            "sort",
+            &["followers", "repositories", "joined"],
+        )
+        .map_err(|e| self.err(e))?;
+
+        // User search has no org qualifier, so the query is not scoped.
+        let response = self
+            .search(
+                "users",
+                &params.query,
+                params.sort.as_deref(),
+                params.order.as_deref(),
+                params.per_page,
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let items: Vec<serde_json::Value> = search_items(&response)
+            .iter()
+            .map(|item| {
+                serde_json::json!({
+                    "login": item.get("login"),
+                    "type": item.get("type"),
+                    "score": item.get("score"),
+                    "url": item.get("html_url"),
+                })
+            })
+            .collect();
+
+        Ok(self.search_result(&params.query, items, &response))
+    }
+
+    #[tool(
+        name = "list_actions_runs",
+        description = "List recent GitHub Actions workflow runs for a repository"
+    )]
+    async fn list_actions_runs(
+        &self,
+        Parameters(params): Parameters<ActionsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        // Validate owner and repo to prevent URL injection in raw route
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let per_page = self.capped_per_page(params.per_page);
+
+        let route = format!(
+            "/repos/{}/{}/actions/runs?per_page={}",
+            owner, params.repo, per_page
+        );
+
+        let response: serde_json::Value = self.api_get(&route).await.map_err(|e| self.err(e))?;
+
+        let runs = response
+            .get("workflow_runs")
+            .and_then(|r| r.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .map(|run| {
+                        serde_json::json!({
+                            "id": run.get("id"),
+                            "name": run.get("name"),
+                            "status": run.get("status"),
+                            "conclusion": run.get("conclusion"),
+                            "branch": run.get("head_branch"),
+                            "event": run.get("event"),
+                            "created_at": run.get("created_at"),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "runs": runs,
+            "count": runs.len(),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "list_run_jobs",
+        description = "List jobs for a GitHub Actions workflow run, including steps, conclusions, and runner labels"
+    )]
+    async fn list_run_jobs(
+        &self,
+        Parameters(params): Parameters<RunJobsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let per_page = self.capped_per_page(params.per_page);
+        let filter = if params.all_attempts.unwrap_or(false) {
+            "all"
+        } else {
+            "latest"
+        };
+        let route = format!(
+            "/repos/{}/{}/actions/runs/{}/jobs?per_page={}&filter={}",
+            owner, params.repo, params.run_id, per_page, filter
+        );
+
+        let response: serde_json::Value = self.api_get(&route).await.map_err(|e| self.err(e))?;
+
+        let jobs = response
+            .get("jobs")
+            .and_then(|j| j.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .map(|job| {
+                        let steps = job
+                            .get("steps")
+                            .and_then(|s| s.as_array())
+                            .map(|steps| {
+                                steps
+                                    .iter()
+                                    .map(|step| {
+                                        serde_json::json!({
+                                            "number": step.get("number"),
+                                            "name": step.get("name"),
+                                            "status": step.get("status"),
+                                            "conclusion": step.get("conclusion"),
+                                        })
+                                    })
+                                    .collect::<Vec<_>>()
+                            })
+                            .unwrap_or_default();
+                        serde_json::json!({
+                            "id": job.get("id"),
+                            "name": job.get("name"),
+                            "status": job.get("status"),
+                            "conclusion": job.get("conclusion"),
+                            "started_at": job.get("started_at"),
+                            "completed_at": job.get("completed_at"),
+                            "runner_name": job.get("runner_name"),
+                            "labels": job.get("labels"),
+                            "steps": steps,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "run_id": params.run_id,
+            "jobs": jobs,
+            "count": jobs.len(),
+            "total_count": response.get("total_count"),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "get_job_logs",
+        description = "Get the log for a GitHub Actions job, narrowed to the failing step by default, with a line window"
+    )]
+    async fn get_job_logs(
+        &self,
+        Parameters(params): Parameters<JobLogsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let job_route = format!(
+            "/repos/{}/{}/actions/jobs/{}",
+            owner, params.repo, params.job_id
+        );
+        let job: serde_json::Value = self.api_get(&job_route).await.map_err(|e| self.err(e))?;
+
+        let logs_route = format!("{}/logs", job_route);
+        let (log, log_truncated) =
+            ratelimit::with_retry(|| self.download_log(&logs_route, MAX_LOG_BYTES))
+                .await
+                .map_err(|e| self.err(e))?;
+        let log = log.trim_start_matches('\u{feff}');
+
+        let steps = job
+            .get("steps")
+            .and_then(|s| s.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let step = match params.step {
+            Some(n) => Some(
+                steps
+                    .iter()
+                    .find(|s| s.get("number").and_then(|v| v.as_u64()) == Some(n))
+                    .ok_or_else(|| {
+                        self.err(McpGithubError::MissingParam(format!(
+                            "job {} has no step {}",
+                            params.job_id, n
+                        )))
+                    })?,
+            ),
+            None => steps
+                .iter()
+                .find(|s| s.get("conclusion").and_then(|c| c.as_str()) == Some("failure")),
+        };
+
+        let lines: Vec<&str> = match step.and_then(|s| {
+            let started = s.get("started_at")?.as_str()?;
+            let completed = s.get("completed_at")?.as_str()?;
+            Some((started, completed))
+        }) {
+            Some((started, completed)) => step_log_lines(log, started, completed),
+            None => log.lines().map(|l| split_log_timestamp(l).1).collect(),
+        };
+
+        let max_lines = params
+            .max_lines
+            .unwrap_or(DEFAULT_LOG_LINES)
+            .clamp(1, MAX_LOG_LINES);
+        let (first_line, window) = line_window(&lines, params.start_line, max_lines);
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "job_id": params.job_id,
+            "job_name": job.get("name"),
+            "conclusion": job.get("conclusion"),
+            "step": step.map(|s| serde_json::json!({
+                "number": s.get("number"),
+                "name": s.get("name"),
+                "conclusion": s.get("conclusion"),
+            })),
+            "total_lines": lines.len(),
+            "log_truncated": log_truncated,
+            "first_line": first_line,
+            "log": window.join("\n"),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "list_commits",
+        description = "List commits on a branch or tag"
+    )]
+    async fn list_commits(
+        &self,
+        Parameters(params): Parameters<ListCommitsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let paging = self
+            .page_request(params.per_page, &params.paging)
+            .map_err(|e| self.err(e))?;
+        let mut route = format!(
+            "/repos/{}/{}/commits?per_page={}&page={}",
+            owner, params.repo, paging.per_page, paging.page
+        );
+        if let Some(ref sha) = params.sha {
+            sanitize_url_value(sha, "sha").map_err(|e| self.err(e))?;
+            route.push_str(&format!("&sha={}", sha));
+        }
+        if let Some(ref author) = params.author {
+            sanitize_url_value(author, "author").map_err(|e| self.err(e))?;
+            route.push_str(&format!("&author={}", author));
+        }
+        if let Some(ref committer) = params.committer {
+            sanitize_url_value(committer, "committer").map_err(|e| self.err(e))?;
+            route.push_str(&format!("&committer={}", committer));
+        }
+        if let Some(ref path) = params.path {
+            sanitize_url_value(path, "path").map_err(|e| self.err(e))?;
+            route.push_str(&format!("&path={}", resources::percent_encode(path, false)));
+        }
+        for (value, field) in [(&params.since, "since"), (&params.until, "until")] {
+            if let Some(value) = value {
+                let timestamp = parse_timestamp(value, field).map_err(|e| self.err(e))?;
+                route.push_str(&format!("&{}={}", field, timestamp));
+            }
+        }
+
+        let first = self.api_get(&route).await.map_err(|e| self.err(e))?;
+        let (response, next_cursor) = self
+            .collect_pages::<serde_json::Value>(&paging, first)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let commits: Vec<serde_json::Value> = response
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "sha": c.get("sha"),
+                    "message": c.pointer("/commit/message"),
+                    "author": c.pointer("/commit/author/name"),
+                    "author_login": c.pointer("/author/login"),
+                    "committer_login": c.pointer("/committer/login"),
+                    "date": c.pointer("/commit/author/date"),
+                    "verified": c.pointer("/commit/verification/verified"),
+                    "verification_reason": c.pointer("/commit/verification/reason"),
+                    "parent_count": c.get("parents").and_then(|p| p.as_array()).map(|p| p.len()),
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "commits": commits,
+            "count": commits.len(),
+            "next_cursor": next_cursor,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "get_commit",
+        description = "Get full commit details including changed files"
+    )]
+    async fn get_commit(
+        &self,
+        Parameters(params): Parameters<CommitRefParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+        sanitize_url_value(&params.sha, "ref").map_err(|e| self.err(e))?;
+
+        let route = format!(
+            "/repos/{}/{}/commits/{}",
+            owner, params.repo, params.sha
+        );
+
+        let c: serde_json::Value = self.api_get(&route).await.map_err(|e| self.err(e))?;
+
+        let files = c
+            .get("files")
+            .and_then(|f| f.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .map(|f| {
+                        serde_json::json!({
+                            "filename": f.get("filename"),
+                            "status": f.get("status"),
+                            "additions": f.get("additions"),
+                            "deletions": f.get("deletions"),
+                            "changes": f.get("changes"),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let file_count = files.len();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "sha": c.get("sha"),
+            "message": c.pointer("/commit/message"),
+            "author": c.pointer("/commit/author/name"),
+            "author_login": c.pointer("/author/login"),
+            "date": c.pointer("/commit/author/date"),
+            "parents": c.get("parents").and_then(|p| p.as_array()).map(|arr| {
+                arr.iter().filter_map(|p| p.get("sha")).collect::<Vec<_>>()
+            }),
+            "stats": c.get("stats"),
+            "files": files,
+            "file_count": file_count,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "compare_refs",
+        description = "Compare two refs: ahead/behind counts, merge base, commits, and per-file changes with optional patches"
+    )]
+    async fn compare_refs(
+        &self,
+        Parameters(params): Parameters<CompareParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+        let (base, head) =
+            split_compare_refs(&params.base, params.head.as_deref()).map_err(|e| self.err(e))?;
+        // Refs in forks are written `owner:ref` (same repo name) or
+        // `owner:repo:ref`; the fork must be allowed too.
+        for value in [&base, &head] {
+            let parts: Vec<&str> = value.split(':').collect();
+            let fork = match parts.as_slice() {
+                [owner, _] => Some((*owner, params.repo.as_str())),
+                [owner, repo, _] => Some((*owner, *repo)),
+                _ => None,
+            };
+            if let Some((owner, repo)) = fork {
+                self.repo_policy
+                    .check(owner, repo)
+                    .map_err(|e| self.err(e))?;
+            }
+        }
+
+        let route = format!(
+            "/repos/{}/{}/compare/{}...{}",
+            owner, params.repo, base, head
+        );
+        let response: serde_json::Value = self.api_get(&route).await.map_err(|e| self.err(e))?;
+
+        let commits: Vec<serde_json::Value> = response
+            .get("commits")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+            .map(|c| {
+                serde_json::json!({
+                    "sha": c.get("sha"),
+                    "message": c.pointer("/commit/message"),
+                    "author": c.pointer("/commit/author/name"),
+                    "author_login": c.pointer("/author/login"),
+                    "date": c.pointer("/commit/author/date"),
+                })
+            })
+            .collect();
+
+        let include_patch = params.include_patch.unwrap_or(false);
+        let mut budget = params.max_patch_bytes.unwrap_or(DEFAULT_MAX_PATCH_BYTES);
+        let mut truncated = false;
+        let files: Vec<serde_json::Value> = response
+            .get("files")
+            .and_then(|f| f.as_array())
+            .into_iter()
+            .flatten()
+            .map(|f| {
+                let mut file = serde_json::json!({
+                    "filename": f.get("filename"),
+                    "previous_filename": f.get("previous_filename"),
+                    "status": f.get("status"),
+                    "additions": f.get("additions"),
+                    "deletions": f.get("deletions"),
+                    "changes": f.get("changes"),
+                });
+                if include_patch {
+                    let patch = f.get("patch").and_then(|p| p.as_str()).map(|p| {
+                        let kept = truncate_utf8(p, budget);
+                        budget -= kept.len();
+                        if kept.len() < p.len() {
+                            truncated = true;
+                        }
+                        kept
+                    });
+                    file["patch"] = serde_json::json!(patch);
+                }
+                file
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "base": base,
+            "head": head,
+            "status": response.get("status"),
+            "ahead_by": response.get("ahead_by"),
+            "behind_by": response.get("behind_by"),
+            "merge_base": response.pointer("/merge_base_commit/sha"),
+            "total_commits": response.get("total_commits"),
+            "commits": commits,
+            "files": files,
+            "patch_truncated": truncated,
+            "url": response.get("html_url"),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "get_checks",
+        description = "Get CI status for a ref or pull request: check runs from all apps merged with commit statuses, plus an overall verdict"
+    )]
+    async fn get_checks(
+        &self,
+        Parameters(params): Parameters<ChecksParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let git_ref = match (params.git_ref, params.pr_number) {
+            (Some(git_ref), None) => {
+                sanitize_url_value(&git_ref, "ref").map_err(|e| self.err(e))?;
+                git_ref
+            }
+            (None, Some(pr_number)) => {
+                let pr: octocrab::models::pulls::PullRequest = self
+                    .api_get(&format!("/repos/{}/{}/pulls/{}", owner, params.repo, pr_number))
+                    .await
+                    .map_err(|e| self.err(e))?;
+                pr.head.sha
+            }
+            _ => {
+                return Err(self.err(McpGithubError::MissingParam(
+                    "exactly one of ref or pr_number is required".to_string(),
+                )))
+            }
+        };
+        let base = format!("/repos/{}/{}/commits/{}", owner, params.repo, git_ref);
+
+        let (runs, runs_incomplete) = self
+            .api_get_all_wrapped(
+                &format!("{}/check-runs", base),
+                "check_runs",
+                MAX_CHECK_PAGES,
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+        let (suites, suites_incomplete) = self
+            .api_get_all_wrapped(
+                &format!("{}/check-suites", base),
+                "check_suites",
+                MAX_CHECK_PAGES,
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+        let (combined, statuses_incomplete) = self
+            .api_get_all_wrapped(&format!("{}/status", base), "statuses", MAX_CHECK_PAGES)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let mut checks: Vec<serde_json::Value> = runs
+            .get("check_runs")
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .map(|run| {
+                serde_json::json!({
+                    "name": run.get("name"),
+                    "source": "check_run",
+                    "app": run.pointer("/app/slug"),
+                    "status": run.get("status"),
+                    "conclusion": run.get("conclusion"),
+                    "details_url": run.get("details_url").or_else(|| run.get("html_url")),
+                })
+            })
+            .collect();
+        // A suite that has been requested but has not created any runs yet
+        // would otherwise be invisible.
+        let waiting_suites = suites
+            .get("check_suites")
+            .and_then(|s| s.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|suite| {
+                suite
+                    .get("latest_check_runs_count")
+                    .and_then(|c| c.as_u64())
+                    == Some(0)
+                    && suite.get("status").and_then(|s| s.as_str()) != Some("completed")
+            });
+        for suite in waiting_suites {
+            checks.push(serde_json::json!({
+                "name": suite.pointer("/app/name"),
+                "source": "check_suite",
+                "app": suite.pointer("/app/slug"),
+                "status": suite.get("status"),
+                "conclusion": suite.get("conclusion"),
+                "details_url": null,
+            }));
+        }
+        for s in combined
+            .get("statuses")
+            .and_then(|s| s.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let state = s.get("state").and_then(|v| v.as_str()).unwrap_or("pending");
+            let (status, conclusion) = match state {
+                "pending" => ("pending", None),
+                "error" => ("completed", Some("failure")),
+                other => ("completed", Some(other)),
+            };
+            checks.push(serde_json::json!({
+                "name": s.get("context"),
+                "source": "status",
+                "app": null,
+                "status": status,
+                "conclusion": conclusion,
+                "description": s.get("description"),
+                "details_url": s.get("target_url"),
+            }));
         }
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "ref": git_ref,
+            "sha": combined.get("sha"),
+            "verdict": checks_verdict(&checks),
+            "checks": checks,
+            "count": checks.len(),
+            "incomplete": runs_incomplete || suites_incomplete || statuses_incomplete,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
     }
-    Ok(())
-}
 
-/// Validate a value for use in URL paths or query params. Unlike
-/// `sanitize_github_name`, this allows slashes (for branch names like
-/// `feature/foo` or file paths like `src/main.rs`).
-fn sanitize_url_value(value: &str, field: &str) -> Result<(), McpGithubError> {
-    if value.is_empty() {
-        return Err(McpGithubError::MissingParam(format!(
-            "{} must not be empty",
-            field
-        )));
+    #[tool(
+        name = "list_branches",
+        description = "List branches in a repository"
+    )]
+    async fn list_branches(
+        &self,
+        Parameters(params): Parameters<RepoPageParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let paging = self
+            .page_request(params.per_page, &params.paging)
+            .map_err(|e| self.err(e))?;
+        let route = format!(
+            "/repos/{}/{}/branches?per_page={}&page={}",
+            owner, params.repo, paging.per_page, paging.page
+        );
+
+        let first = self.api_get(&route).await.map_err(|e| self.err(e))?;
+        let (response, next_cursor) = self
+            .collect_pages::<serde_json::Value>(&paging, first)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let branches: Vec<serde_json::Value> = response
+            .iter()
+            .map(|b| {
+                serde_json::json!({
+                    "name": b.get("name"),
+                    "sha": b.pointer("/commit/sha"),
+                    "protected": b.get("protected"),
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "branches": branches,
+            "count": branches.len(),
+            "next_cursor": next_cursor,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
     }
-    for ch in ['?', '#', '&', '\0', '\n', '\r', '\t'] {
-        if value.contains(ch) {
-            return Err(McpGithubError::MissingParam(format!(
-                "{} contains invalid character",
-                field
+
+    #[tool(
+        name = "get_file_contents",
+        description = "Get file content from a repository at a specific ref"
+    )]
+    async fn get_file_contents(
+        &self,
+        Parameters(params): Parameters<FileContentsParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        let response = self
+            .fetch_file(
+                &owner,
+                &params.repo,
+                &params.path,
+                params.git_ref.as_deref(),
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let content = match decode_content(&response) {
+            Some(bytes) => {
+                String::from_utf8(bytes).unwrap_or_else(|_| "[binary content]".to_string())
+            }
+            None => String::new(),
+        };
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "path": response.get("path"),
+            "name": response.get("name"),
+            "size": response.get("size"),
+            "encoding": response.get("encoding"),
+            "content": content,
+            "sha": response.get("sha"),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "list_directory",
+        description = "List the files and subdirectories of a repository directory with type, size, and SHA"
+    )]
+    async fn list_directory(
+        &self,
+        Parameters(params): Parameters<ListDirectoryParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+        let path = params.path.as_deref().unwrap_or("").trim_matches('/');
+        if !path.is_empty() {
+            sanitize_url_value(path, "path").map_err(|e| self.err(e))?;
+        }
+        if let Some(ref git_ref) = params.git_ref {
+            sanitize_url_value(git_ref, "ref").map_err(|e| self.err(e))?;
+        }
+
+        let route = contents_route(&owner, &params.repo, path, params.git_ref.as_deref());
+        let response: serde_json::Value = self.api_get(&route).await.map_err(|e| self.err(e))?;
+        let Some(items) = response.as_array() else {
+            return Err(self.err(McpGithubError::MissingParam(format!(
+                "{} is a file; use get_file_contents",
+                path
+            ))));
+        };
+
+        let entries: Vec<serde_json::Value> = items
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "name": e.get("name"),
+                    "path": e.get("path"),
+                    "type": e.get("type"),
+                    "size": e.get("size"),
+                    "sha": e.get("sha"),
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "path": path,
+            "ref": params.git_ref,
+            "entries": entries,
+            "count": entries.len(),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "get_tree",
+        description = "Get the repository file tree (recursive by default), optionally limited to a directory and filtered by glob"
+    )]
+    async fn get_tree(
+        &self,
+        Parameters(params): Parameters<TreeParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+        let git_ref = params.git_ref.as_deref().unwrap_or("HEAD");
+        sanitize_url_value(git_ref, "ref").map_err(|e| self.err(e))?;
+        let prefix = params.path.as_deref().unwrap_or("").trim_matches('/');
+        if !prefix.is_empty() {
+            sanitize_url_value(prefix, "path").map_err(|e| self.err(e))?;
+        }
+        let glob = params
+            .pattern
+            .as_deref()
+            .map(|p| compile_glob(p, "pattern"))
+            .transpose()
+            .map_err(|e| self.err(e))?;
+        let limit = params
+            .limit
+            .map(|l| l as usize)
+            .unwrap_or(MAX_LIST_LIMIT)
+            .clamp(1, MAX_LIST_LIMIT);
+
+        // Fetch the subtree itself when a directory is given, so large
+        // repositories are less likely to hit GitHub's truncation limit.
+        let tree_sha = if prefix.is_empty() {
+            git_ref.to_string()
+        } else {
+            let (parent, name) = prefix.rsplit_once('/').unwrap_or(("", prefix));
+            let route = contents_route(&owner, &params.repo, parent, Some(git_ref));
+            let listing: serde_json::Value = self.api_get(&route).await.map_err(|e| self.err(e))?;
+            listing
+                .as_array()
+                .and_then(|items| {
+                    items.iter().find(|e| {
+                        e.get("name").and_then(|n| n.as_str()) == Some(name)
+                            && e.get("type").and_then(|t| t.as_str()) == Some("dir")
+                    })
+                })
+                .and_then(|e| e.get("sha").and_then(|s| s.as_str()))
+                .map(String::from)
+                .ok_or_else(|| {
+                    self.err(McpGithubError::MissingParam(format!(
+                        "{} is not a directory at {}",
+                        prefix, git_ref
+                    )))
+                })?
+        };
+
+        let mut route = format!("/repos/{}/{}/git/trees/{}", owner, params.repo, tree_sha);
+        if params.recursive.unwrap_or(true) {
+            route.push_str("?recursive=1");
+        }
+        let tree: serde_json::Value = self.api_get(&route).await.map_err(|e| self.err(e))?;
+        let truncated = tree
+            .get("truncated")
+            .and_then(|t| t.as_bool())
+            .unwrap_or(false);
+        let (entries, limited) = tree_entries(&tree, prefix, glob.as_ref(), limit);
+
+        let mut result = serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "ref": git_ref,
+            "path": prefix,
+            "entries": entries,
+            "count": entries.len(),
+            "truncated": truncated || limited,
+        });
+        if truncated {
+            result["hint"] = serde_json::json!(
+                "GitHub truncated this tree; narrow it with path or list subdirectories separately"
+            );
+        } else if limited {
+            result["hint"] = serde_json::json!(format!(
+                "Stopped after {} entries; raise limit or narrow with path or pattern",
+                limit
+            ));
+        }
+
+        let text = serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "get_blame",
+        description = "Show which commit last changed each line of a file, grouped by commit with author, date, and message"
+    )]
+    async fn get_blame(
+        &self,
+        Parameters(params): Parameters<BlameParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+        let git_ref = params.git_ref.as_deref().unwrap_or("HEAD");
+        sanitize_url_value(git_ref, "ref").map_err(|e| self.err(e))?;
+        sanitize_url_value(&params.path, "path").map_err(|e| self.err(e))?;
+        let start = params.start_line.unwrap_or(1).max(1);
+        let end = params.end_line.unwrap_or(u64::MAX);
+        if end < start {
+            return Err(self.err(McpGithubError::MissingParam(
+                "end_line must not be before start_line".to_string(),
             )));
         }
+
+        let data = self
+            .graphql(
+                BLAME_QUERY,
+                serde_json::json!({
+                    "owner": owner,
+                    "repo": params.repo,
+                    "ref": git_ref,
+                    "path": params.path,
+                }),
+            )
+            .await
+            .map_err(|e| self.err(e))?;
+        let commit = data
+            .pointer("/repository/object")
+            .filter(|o| !o.is_null())
+            .ok_or_else(|| {
+                self.err(McpGithubError::NotFound(format!(
+                    "commit {} in {}/{}",
+                    git_ref, owner, params.repo
+                )))
+            })?;
+        let ranges = commit
+            .pointer("/blame/ranges")
+            .and_then(|r| r.as_array())
+            .ok_or_else(|| {
+                self.err(McpGithubError::MissingParam(format!(
+                    "ref '{}' does not name a commit",
+                    git_ref
+                )))
+            })?;
+
+        let commits = group_blame(ranges, start, end);
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "path": params.path,
+            "ref": git_ref,
+            "sha": commit.get("oid"),
+            "commits": commits,
+            "count": commits.len(),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
     }
-    Ok(())
-}
 
-// -- MCP tool handlers (thin wrappers calling do_* methods) --
+    #[tool(
+        name = "list_releases",
+        description = "List releases for a repository"
+    )]
+    async fn list_releases(
+        &self,
+        Parameters(params): Parameters<RepoPageParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let paging = self
+            .page_request(params.per_page, &params.paging)
+            .map_err(|e| self.err(e))?;
+        let route = format!(
+            "/repos/{}/{}/releases?per_page={}&page={}",
+            owner, params.repo, paging.per_page, paging.page
+        );
+
+        let first = self.api_get(&route).await.map_err(|e| self.err(e))?;
+        let (response, next_cursor) = self
+            .collect_pages::<serde_json::Value>(&paging, first)
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let releases: Vec<serde_json::Value> = response
+            .iter()
+            .map(|r| {
+                let assets = r
+                    .get("assets")
+                    .and_then(|a| a.as_array())
+                    .map(|a| a.len())
+                    .unwrap_or(0);
+                serde_json::json!({
+                    "tag": r.get("tag_name"),
+                    "name": r.get("name"),
+                    "author": r.pointer("/author/login"),
+                    "prerelease": r.get("prerelease"),
+                    "draft": r.get("draft"),
+                    "published_at": r.get("published_at"),
+                    "asset_count": assets,
+                })
+            })
+            .collect();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "repo": format!("{}/{}", owner, params.repo),
+            "releases": releases,
+            "count": releases.len(),
+            "next_cursor": next_cursor,
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
 
-#[tool_router]
-impl McpGithubServer {
     #[tool(
-        name = "list_repos",
-        description = "List repositories for a user or organization"
+        name = "list_tags",
+        description = "List tags in a repository"
     )]
-    async fn list_repos(
+    async fn list_tags(
         &self,
-        Parameters(params): Parameters<OwnerParam>,
+        Parameters(params): Parameters<RepoPageParams>,
     ) -> Result<CallToolResult, ErrorData> {
         let owner = self
-            .resolve_owner(params.owner.as_deref())
+            .resolve_repo(params.owner.as_deref(), &params.repo)
             .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
 
-        let per_page = self.capped_per_page(None);
-
-        let page = self
-            .github
-            .orgs(&owner)
-            .list_repos()
-            .per_page(per_page)
-            .send()
-            .await;
+        let paging = self
+            .page_request(params.per_page, &params.paging)
+            .map_err(|e| self.err(e))?;
+        let route = format!(
+            "/repos/{}/{}/tags?per_page={}&page={}",
+            owner, params.repo, paging.per_page, paging.page
+        );
 
-        // If org fails, try as user
-        let repos = match page {
-            Ok(page) => page.items,
-            Err(_) => {
-                self.github
-                    .users(&owner)
-                    .repos()
-                    .per_page(per_page)
-                    .send()
-                    .await
-                    .map_err(|e| self.err(McpGithubError::GitHub(e)))?
-                    .items
-            }
-        };
+        let first = self.api_get(&route).await.map_err(|e| self.err(e))?;
+        let (response, next_cursor) = self
+            .collect_pages::<serde_json::Value>(&paging, first)
+            .await
+            .map_err(|e| self.err(e))?;
 
-        let results: Vec<serde_json::Value> = repos
+        let tags: Vec<serde_json::Value> = response
             .iter()
-            .map(|r| {
+            .map(|t| {
                 serde_json::json!({
-                    "name": r.name,
-                    "full_name": r.full_name.as_deref().unwrap_or(""),
-                    "description": r.description.as_deref().unwrap_or(""),
-                    "language": r.language.as_ref().map(|l| l.to_string()).unwrap_or_default(),
-                    "stars": r.stargazers_count.unwrap_or(0),
-                    "forks": r.forks_count.unwrap_or(0),
-                    "private": r.private.unwrap_or(false),
+                    "name": t.get("name"),
+                    "sha": t.pointer("/commit/sha"),
                 })
             })
             .collect();
 
         let text = serde_json::to_string_pretty(&serde_json::json!({
-            "owner": owner,
-            "repos": results,
-            "count": results.len(),
+            "repo": format!("{}/{}", owner, params.repo),
+            "tags": tags,
+            "count": tags.len(),
+            "next_cursor": next_cursor,
         }))
         .unwrap_or_else(|_| "{}".to_string());
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
     #[tool(
-        name = "get_repo",
-        description = "Get repository info including description, stars, forks, language, and default branch"
+        name = "graphql_query",
+        description = "Run a GitHub GraphQL API query (e.g. Projects v2, discussions, sub-issues) and return its data and errors"
     )]
-    async fn get_repo(
+    async fn graphql_query(
         &self,
-        Parameters(params): Parameters<RepoParams>,
+        Parameters(params): Parameters<GraphqlParams>,
     ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
+        if self.repo_policy.is_restricted() {
+            return Err(self.err(McpGithubError::Forbidden(
+                "graphql_query can reach any repository, so it is disabled while \
+                 --allow-repos or --deny-repos is set"
+                    .to_string(),
+            )));
+        }
+        let variables = params.variables.unwrap_or_else(|| serde_json::json!({}));
+        if !variables.is_object() {
+            return Err(self.err(McpGithubError::MissingParam(
+                "variables must be a JSON object".to_string(),
+            )));
+        }
+        graphql::check_query(&params.query, &variables, self.allow_writes)
             .map_err(|e| self.err(e))?;
 
-        let repo = self
-            .github
-            .repos(&owner, &params.repo)
-            .get()
+        let response = self
+            .graphql_response(&params.query, variables)
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+            .map_err(|e| self.err(e))?;
 
         let text = serde_json::to_string_pretty(&serde_json::json!({
-            "name": repo.name,
-            "full_name": repo.full_name,
-            "description": repo.description,
-            "language": repo.language,
-            "default_branch": repo.default_branch,
-            "stars": repo.stargazers_count,
-            "forks": repo.forks_count,
-            "open_issues": repo.open_issues_count,
-            "private": repo.private,
-            "created_at": repo.created_at.map(|t| t.to_string()),
-            "updated_at": repo.updated_at.map(|t| t.to_string()),
+            "data": response.get("data"),
+            "errors": response.get("errors"),
         }))
         .unwrap_or_else(|_| "{}".to_string());
+        if text.len() > graphql::MAX_RESPONSE_BYTES {
+            return Err(self.err(McpGithubError::LimitExceeded(format!(
+                "response is {} bytes; the limit is {}. Request fewer fields or smaller pages",
+                text.len(),
+                graphql::MAX_RESPONSE_BYTES
+            ))));
+        }
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
     #[tool(
-        name = "list_issues",
-        description = "List issues in a repository, optionally filtered by state and labels"
+        name = "get_rate_limit",
+        description = "Show remaining GitHub API quota per resource (core, search, graphql, ...) and when each resets"
     )]
-    async fn list_issues(
+    async fn get_rate_limit(&self) -> Result<CallToolResult, ErrorData> {
+        // Always ask GitHub directly; this endpoint is free and must not be cached
+        let body: serde_json::Value = self
+            .call_github("rate limit", || self.github.get("/rate_limit", None::<&()>))
+            .await
+            .map_err(|e| self.err(e))?;
+
+        let resources: serde_json::Map<String, serde_json::Value> = body
+            .get("resources")
+            .and_then(|r| r.as_object())
+            .map(|resources| {
+                resources
+                    .iter()
+                    .map(|(name, r)| {
+                        let reset_at = r
+                            .get("reset")
+                            .and_then(|v| v.as_i64())
+                            .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                            .map(|t| t.to_rfc3339());
+                        (
+                            name.clone(),
+                            serde_json::json!({
+                                "limit": r.get("limit"),
+                                "remaining": r.get("remaining"),
+                                "used": r.get("used"),
+                                "reset_at": reset_at,
+                            }),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let text = serde_json::to_string_pretty(&serde_json::json!({
+            "resources": resources,
+            "cache": self.cache.stats(),
+        }))
+        .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+}
+
+// -- Write tools, registered only when writes are enabled --
+
+#[tool_router(router = issue_write_router)]
+impl McpGithubServer {
+    #[tool(
+        name = "create_issue",
+        description = "Create a new issue with optional body, assignees, labels, and milestone"
+    )]
+    async fn create_issue(
         &self,
-        Parameters(params): Parameters<ListIssuesParams>,
+        Parameters(params): Parameters<CreateIssueParams>,
     ) -> Result<CallToolResult, ErrorData> {
         let owner = self
-            .resolve_owner(params.owner.as_deref())
+            .resolve_repo(params.owner.as_deref(), &params.repo)
             .map_err(|e| self.err(e))?;
 
-        let per_page = self.capped_per_page(params.per_page);
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
 
-        let issue_handler = self.github.issues(&owner, &params.repo);
-        let mut request = issue_handler.list().per_page(per_page);
+        let body = IssueBody {
+            title: Some(&params.title),
+            body: params.body.as_deref(),
+            assignees: params.assignees.as_deref(),
+            labels: params.labels.as_deref(),
+            milestone: params.milestone,
+            ..IssueBody::default()
+        };
+        let issue: octocrab::models::issues::Issue = self
+            .api_send(
+                http::Method::POST,
+                &format!("/repos/{}/{}/issues", owner, params.repo),
+                &body,
+            )
+            .await
+            .map_err(|e| self.err(e))?;
 
-        if let Some(ref state) = params.state {
-            request = match state.as_str() {
-                "open" => request.state(octocrab::params::State::Open),
-                "closed" => request.state(octocrab::params::State::Closed),
-                "all" => request.state(octocrab::params::State::All),
-                _ => request,
-            };
-        }
+        let text = serde_json::to_string_pretty(&issue_summary(&issue))
+            .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
 
-        let label_list: Vec<String>;
-        if let Some(ref labels) = params.labels {
-            label_list = labels.split(',').map(|s| s.trim().to_string()).collect();
-            request = request.labels(&label_list);
-        }
+    #[tool(
+        name = "update_issue",
+        description = "Edit an issue's title, body, assignees, labels, or milestone, or close/reopen it via state"
+    )]
+    async fn update_issue(
+        &self,
+        Parameters(params): Parameters<UpdateIssueParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        let state = params
+            .state
+            .as_deref()
+            .map(parse_issue_state)
+            .transpose()
+            .map_err(|e| self.err(e))?;
+        let state_reason = params
+            .state_reason
+            .as_deref()
+            .map(parse_state_reason)
+            .transpose()
+            .map_err(|e| self.err(e))?;
+
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
 
-        let issues = request
-            .send()
+        let body = IssueBody {
+            title: params.title.as_deref(),
+            body: params.body.as_deref(),
+            state,
+            state_reason,
+            assignees: params.assignees.as_deref(),
+            labels: params.labels.as_deref(),
+            milestone: params.milestone,
+        };
+        let issue: octocrab::models::issues::Issue = self
+            .api_send(
+                http::Method::PATCH,
+                &format!(
+                    "/repos/{}/{}/issues/{}",
+                    owner, params.repo, params.issue_number
+                ),
+                &body,
+            )
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+            .map_err(|e| self.err(e))?;
 
-        let results: Vec<serde_json::Value> = issues
-            .items
-            .iter()
-            .map(|i| {
-                let labels: Vec<String> = i.labels.iter().map(|l| l.name.clone()).collect();
-                serde_json::json!({
-                    "number": i.number,
-                    "title": i.title,
-                    "state": format_state(&i.state),
-                    "author": i.user.login,
-                    "labels": labels,
-                    "comments": i.comments,
-                    "created_at": i.created_at.to_string(),
-                })
-            })
-            .collect();
+        let text = serde_json::to_string_pretty(&issue_summary(&issue))
+            .unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "add_issue_comment",
+        description = "Add a comment to an issue or pull request"
+    )]
+    async fn add_issue_comment(
+        &self,
+        Parameters(params): Parameters<IssueCommentParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let owner = self
+            .resolve_repo(params.owner.as_deref(), &params.repo)
+            .map_err(|e| self.err(e))?;
+
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+
+        let comment: octocrab::models::issues::Comment = self
+            .api_send(
+                http::Method::POST,
+                &format!(
+                    "/repos/{}/{}/issues/{}/comments",
+                    owner, params.repo, params.issue_number
+                ),
+                &serde_json::json!({ "body": params.body }),
+            )
+            .await
+            .map_err(|e| self.err(e))?;
 
         let text = serde_json::to_string_pretty(&serde_json::json!({
-            "repo": format!("{}/{}", owner, params.repo),
-            "issues": results,
-            "count": results.len(),
+            "id": comment.id,
+            "issue_number": params.issue_number,
+            "author": comment.user.login,
+            "url": comment.html_url.to_string(),
+            "created_at": comment.created_at.to_string(),
         }))
         .unwrap_or_else(|_| "{}".to_string());
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
+}
+
+// -- Prompts --
+//
+// Each prompt gathers data through the read tools above and embeds it in a
+// single user message, so the rendered prompt is self-contained.
+
+#[prompt_router]
+impl McpGithubServer {
+    #[prompt(
+        name = "review_pull_request",
+        description = "Review a pull request using its details and changed files"
+    )]
+    async fn review_pull_request_prompt(
+        &self,
+        Parameters(args): Parameters<PullPromptArgs>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let owner = self
+            .resolve_repo(args.owner.as_deref(), &args.repo)
+            .map_err(|e| self.err(e))?;
+        let pull = self
+            .get_pull(Parameters(PullParams {
+                owner: Some(owner.clone()),
+                repo: args.repo.clone(),
+                pr_number: args.number,
+            }))
+            .await?;
+        let files = self
+            .list_pull_files(Parameters(PullFilesParams {
+                owner: Some(owner.clone()),
+                repo: args.repo.clone(),
+                pr_number: args.number,
+                path: None,
+                max_patch_bytes: None,
+            }))
+            .await?;
+
+        let text = format!(
+            "Review pull request {owner}/{repo}#{number}.\n\n\
+             Pull request:\n```json\n{pull}\n```\n\n\
+             Changed files:\n```json\n{files}\n```\n\n\
+             Summarize what the change does, then list problems by severity \
+             (bugs, security, missing tests, style), citing file and line. \
+             If patches were truncated, use get_file_contents or \
+             list_pull_files with a path filter before commenting on those files. \
+             Finish with a recommendation: approve, request changes, or comment.",
+            owner = owner,
+            repo = args.repo,
+            number = args.number,
+            pull = tool_text(&pull),
+            files = tool_text(&files),
+        );
+        Ok(GetPromptResult {
+            description: Some(format!("Review of {}/{}#{}", owner, args.repo, args.number)),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    #[prompt(
+        name = "triage_issue",
+        description = "Triage an issue: classify it, suggest labels, and propose next steps"
+    )]
+    async fn triage_issue_prompt(
+        &self,
+        Parameters(args): Parameters<IssuePromptArgs>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let owner = self
+            .resolve_repo(args.owner.as_deref(), &args.repo)
+            .map_err(|e| self.err(e))?;
+        let issue = self
+            .get_issue(Parameters(IssueParams {
+                owner: Some(owner.clone()),
+                repo: args.repo.clone(),
+                issue_number: args.number,
+            }))
+            .await?;
+
+        let text = format!(
+            "Triage issue {owner}/{repo}#{number}.\n\n\
+             Issue:\n```json\n{issue}\n```\n\n\
+             Classify it as a bug, feature request, question, or documentation \
+             problem and estimate its severity. Suggest labels, note any missing \
+             information the reporter should provide (versions, reproduction \
+             steps, logs), and check for likely duplicates with list_issues. \
+             End with a short proposed reply to the reporter.",
+            owner = owner,
+            repo = args.repo,
+            number = args.number,
+            issue = tool_text(&issue),
+        );
+        Ok(GetPromptResult {
+            description: Some(format!("Triage of {}/{}#{}", owner, args.repo, args.number)),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    #[prompt(
+        name = "summarize_ci_failure",
+        description = "Explain why a GitHub Actions run failed, defaulting to the most recent failure"
+    )]
+    async fn summarize_ci_failure_prompt(
+        &self,
+        Parameters(args): Parameters<CiFailurePromptArgs>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let owner = self
+            .resolve_repo(args.owner.as_deref(), &args.repo)
+            .map_err(|e| self.err(e))?;
+        let runs = self
+            .list_actions_runs(Parameters(ActionsParams {
+                owner: Some(owner.clone()),
+                repo: args.repo.clone(),
+                status: None,
+                per_page: None,
+            }))
+            .await?;
+        let runs_text = tool_text(&runs);
+
+        let run_id = args.number.or_else(|| latest_failed_run(&runs_text));
+        let text = match run_id {
+            Some(run_id) => {
+                let jobs = self
+                    .list_run_jobs(Parameters(RunJobsParams {
+                        owner: Some(owner.clone()),
+                        repo: args.repo.clone(),
+                        run_id,
+                        all_attempts: None,
+                        per_page: None,
+                    }))
+                    .await?;
+                format!(
+                    "Summarize why workflow run {run_id} in {owner}/{repo} failed.\n\n\
+                     Recent runs:\n```json\n{runs}\n```\n\n\
+                     Jobs of run {run_id}:\n```json\n{jobs}\n```\n\n\
+                     For each failed job, fetch the failing step with get_job_logs \
+                     and quote the relevant error lines. Say whether the failure \
+                     looks like a code problem, a flaky test, or an infrastructure \
+                     issue, whether it also fails on other recent runs, and what \
+                     change would most likely fix it.",
+                    run_id = run_id,
+                    owner = owner,
+                    repo = args.repo,
+                    runs = runs_text,
+                    jobs = tool_text(&jobs),
+                )
+            }
+            None => format!(
+                "No failed workflow runs were found among the recent runs of {owner}/{repo}.\n\n\
+                 Recent runs:\n```json\n{runs}\n```\n\n\
+                 Summarize the current CI status and point out anything still \
+                 queued or in progress.",
+                owner = owner,
+                repo = args.repo,
+                runs = runs_text,
+            ),
+        };
+        Ok(GetPromptResult {
+            description: Some(format!("CI failure summary for {}/{}", owner, args.repo)),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    #[prompt(
+        name = "draft_release_notes",
+        description = "Draft release notes from the commits since the latest release"
+    )]
+    async fn draft_release_notes_prompt(
+        &self,
+        Parameters(args): Parameters<ReleaseNotesPromptArgs>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let owner = self
+            .resolve_repo(args.owner.as_deref(), &args.repo)
+            .map_err(|e| self.err(e))?;
+        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
+        sanitize_github_name(&args.repo, "repo").map_err(|e| self.err(e))?;
+        // Without a release every commit is new; the limit below still applies
+        let latest = match self
+            .api_get::<serde_json::Value>(&format!(
+                "/repos/{}/{}/releases/latest",
+                owner, args.repo
+            ))
+            .await
+        {
+            Ok(release) => Some(release),
+            Err(McpGithubError::NotFound(_)) => None,
+            Err(e) => return Err(self.err(e)),
+        };
+        let since = latest
+            .as_ref()
+            .and_then(|r| r.get("published_at"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let commits = self
+            .list_commits(Parameters(ListCommitsParams {
+                owner: Some(owner.clone()),
+                repo: args.repo.clone(),
+                sha: args.sha.clone(),
+                author: None,
+                committer: None,
+                path: None,
+                since,
+                until: None,
+                per_page: Some(100),
+                paging: PageParams {
+                    limit: Some(RELEASE_NOTES_COMMITS),
+                    ..PageParams::default()
+                },
+            }))
+            .await?;
+
+        let text = format!(
+            "Draft release notes for the next release of {owner}/{repo}{branch}.\n\n\
+             Latest release:\n```json\n{release}\n```\n\n\
+             Commits since it was published, newest first:\n```json\n{commits}\n```\n\n\
+             Group changes under Features, Fixes, and Other, write one line per \
+             user-visible change, credit authors by login, and call out breaking \
+             changes first. Suggest the next version number following semver.",
+            owner = owner,
+            repo = args.repo,
+            branch = args
+                .sha
+                .as_deref()
+                .map(|b| format!(" from {}", b))
+                .unwrap_or_default(),
+            release = latest
+                .map(|r| {
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "tag": r.get("tag_name"),
+                        "name": r.get("name"),
+                        "published_at": r.get("published_at"),
+                    }))
+                    .unwrap_or_else(|_| "{}".to_string())
+                })
+                .unwrap_or_else(|| "null".to_string()),
+            commits = tool_text(&commits),
+        );
+        Ok(GetPromptResult {
+            description: Some(format!("Release notes draft for {}/{}", owner, args.repo)),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+}
+
+/// Most commits since the latest release fetched for `draft_release_notes`.
+const RELEASE_NOTES_COMMITS: u32 = 200;
+
+/// Concatenate the text content of a tool result, for embedding in a prompt.
+fn tool_text(result: &CallToolResult) -> String {
+    result
+        .content
+        .iter()
+        .filter_map(|c| c.as_text().map(|t| t.text.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// ID of the newest failed run in a `list_actions_runs` result.
+fn latest_failed_run(runs: &str) -> Option<u64> {
+    let value: serde_json::Value = serde_json::from_str(runs).ok()?;
+    value
+        .get("runs")?
+        .as_array()?
+        .iter()
+        .find(|r| r.get("conclusion").and_then(|c| c.as_str()) == Some("failure"))
+        .and_then(|r| r.get("id")?.as_u64())
+}
+
+/// The non-empty `errors` array of a GraphQL response.
+fn graphql_errors(response: &serde_json::Value) -> Option<&[serde_json::Value]> {
+    response
+        .get("errors")
+        .and_then(|e| e.as_array())
+        .map(Vec::as_slice)
+        .filter(|e| !e.is_empty())
+}
+
+/// Group GraphQL blame ranges by commit, in order of first appearance,
+/// keeping only lines between `start` and `end` (inclusive).
+fn group_blame(ranges: &[serde_json::Value], start: u64, end: u64) -> Vec<serde_json::Value> {
+    let mut commits: Vec<serde_json::Value> = Vec::new();
+    for range in ranges {
+        let (Some(first), Some(last)) = (
+            range.get("startingLine").and_then(|v| v.as_u64()),
+            range.get("endingLine").and_then(|v| v.as_u64()),
+        ) else {
+            continue;
+        };
+        let (first, last) = (first.max(start), last.min(end));
+        if first > last {
+            continue;
+        }
+        let commit = range.get("commit").cloned().unwrap_or_default();
+        let sha = commit.get("oid").cloned().unwrap_or_default();
+        let entry = match commits.iter_mut().position(|c| c["sha"] == sha) {
+            Some(i) => &mut commits[i],
+            None => {
+                commits.push(serde_json::json!({
+                    "sha": sha,
+                    "author": commit.pointer("/author/name"),
+                    "author_login": commit.pointer("/author/user/login"),
+                    "date": commit.pointer("/author/date").or_else(|| commit.get("committedDate")),
+                    "message": commit.get("messageHeadline"),
+                    "lines": 0,
+                    "ranges": [],
+                }));
+                commits.last_mut().expect("just pushed")
+            }
+        };
+        entry["lines"] = serde_json::json!(entry["lines"].as_u64().unwrap_or(0) + last - first + 1);
+        if let Some(list) = entry["ranges"].as_array_mut() {
+            list.push(serde_json::json!([first, last]));
+        }
+    }
+    commits
+}
+
+/// Overall verdict for a set of normalized checks: `failure` if any check
+/// failed, else `pending` while any is unfinished, else `success`. `none`
+/// means nothing reported on the commit.
+fn checks_verdict(checks: &[serde_json::Value]) -> &'static str {
+    if checks.is_empty() {
+        return "none";
+    }
+    let failed = checks.iter().any(|c| {
+        matches!(
+            c.get("conclusion").and_then(|v| v.as_str()),
+            Some("failure" | "cancelled" | "timed_out" | "action_required" | "startup_failure")
+        )
+    });
+    let pending = checks
+        .iter()
+        .any(|c| c.get("status").and_then(|v| v.as_str()) != Some("completed"));
+    if failed {
+        "failure"
+    } else if pending {
+        "pending"
+    } else {
+        "success"
+    }
+}
+
+/// Fold a pull request's reviews (oldest first) into each reviewer's latest
+/// verdict. As on GitHub, a comment-only review does not replace an earlier
+/// approval or change request, and dismissed reviews no longer count.
+fn review_summary(reviews: &[serde_json::Value]) -> serde_json::Value {
+    let mut latest: Vec<(&str, &str)> = Vec::new();
+    for review in reviews {
+        let (Some(login), Some(state)) = (
+            review.pointer("/user/login").and_then(|l| l.as_str()),
+            review.get("state").and_then(|s| s.as_str()),
+        ) else {
+            continue;
+        };
+        let slot = latest.iter_mut().find(|(l, _)| *l == login);
+        match (state, slot) {
+            ("PENDING", _) => {}
+            ("COMMENTED", Some(_)) => {}
+            (_, Some(slot)) => slot.1 = state,
+            (_, None) => latest.push((login, state)),
+        }
+    }
+
+    let with_state = |wanted: &str| -> Vec<&str> {
+        latest
+            .iter()
+            .filter(|(_, s)| *s == wanted)
+            .map(|(l, _)| *l)
+            .collect()
+    };
+    let approved = with_state("APPROVED");
+    let changes_requested = with_state("CHANGES_REQUESTED");
+    let decision = if !changes_requested.is_empty() {
+        "changes_requested"
+    } else if !approved.is_empty() {
+        "approved"
+    } else {
+        "none"
+    };
+    serde_json::json!({
+        "decision": decision,
+        "approved": approved,
+        "changes_requested": changes_requested,
+        "commented": with_state("COMMENTED"),
+        "total": reviews.len(),
+    })
+}
+
+/// Summarize an issue returned from a write call.
+fn issue_summary(issue: &octocrab::models::issues::Issue) -> serde_json::Value {
+    let labels: Vec<String> = issue.labels.iter().map(|l| l.name.clone()).collect();
+    let assignees: Vec<String> = issue.assignees.iter().map(|a| a.login.clone()).collect();
+    serde_json::json!({
+        "number": issue.number,
+        "title": issue.title,
+        "state": format_state(&issue.state),
+        "labels": labels,
+        "assignees": assignees,
+        "milestone": issue.milestone.as_ref().map(|m| m.number),
+        "url": issue.html_url.to_string(),
+    })
+}
+
+#[prompt_handler]
+impl ServerHandler for McpGithubServer {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let tcc = ToolCallContext::new(self, request, context);
+        match self.tool_router.call(tcc).await {
+            Err(e) if self.error_results => tool_error_result(e),
+            result => result,
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        Ok(ListToolsResult {
+            tools: self.tool_router.list_all(),
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
+    fn get_tool(&self, name: &str) -> Option<Tool> {
+        self.tool_router.get(name).cloned()
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        let tools = self.tool_router.list_all();
+        let tool_names: Vec<&str> = tools.iter().map(|t| t.name.as_ref()).collect();
+        let prompts = self.prompt_router.list_all();
+        let prompt_names: Vec<&str> = prompts.iter().map(|p| p.name.as_str()).collect();
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_prompts()
+                .build(),
+            server_info: Implementation {
+                name: "mcp-github".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                ..Default::default()
+            },
+            instructions: Some(toolsets::instructions(&tool_names, &prompt_names)),
+        }
+    }
 
-    #[tool(
-        name = "get_issue",
-        description = "Get issue details including body and comments"
-    )]
-    async fn get_issue(
+    async fn list_resources(
         &self,
-        Parameters(params): Parameters<IssueParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
-
-        let issue = self
-            .github
-            .issues(&owner, &params.repo)
-            .get(params.issue_number)
+        request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let cursor = request.as_ref().and_then(|r| r.cursor.as_deref());
+        self.list_repo_resources(cursor)
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+            .map_err(|e| self.err(e))
+    }
 
-        // Fetch comments
-        let comments = self
-            .github
-            .issues(&owner, &params.repo)
-            .list_comments(params.issue_number)
-            .send()
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let template = RawResourceTemplate {
+            uri_template: resources::BLOB_URI_TEMPLATE.to_string(),
+            name: "file".to_string(),
+            title: Some("Repository file".to_string()),
+            description: Some(
+                "A file in a GitHub repository at a branch, tag, or commit. \
+                 Percent-encode slashes in the ref (feature%2Fx)."
+                    .to_string(),
+            ),
+            mime_type: None,
+            icons: None,
+        };
+        Ok(ListResourceTemplatesResult::with_all_items(vec![
+            template.no_annotation()
+        ]))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        self.read_repo_resource(&request.uri)
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+            .map_err(|e| self.err(e))
+    }
+}
 
-        let comment_items: Vec<serde_json::Value> = comments
-            .items
-            .iter()
-            .map(|c| {
-                serde_json::json!({
-                    "author": c.user.login,
-                    "body": c.body.as_deref().unwrap_or(""),
-                    "created_at": c.created_at.to_string(),
-                })
-            })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn make_server(default_owner: Option<String>, max_results: u32) -> McpGithubServer {
+        let github = octocrab::Octocrab::default();
+        McpGithubServer::new(github, default_owner, max_results)
+    }
+
+    // Note: Octocrab::default() requires a Tokio runtime (tower::Buffer),
+    // so these tests must be async even though they don't await anything.
+
+    fn tool_names(server: &McpGithubServer) -> Vec<String> {
+        let mut names: Vec<String> = server
+            .tool_router
+            .list_all()
+            .into_iter()
+            .map(|t| t.name.to_string())
             .collect();
+        names.sort();
+        names
+    }
 
-        let labels: Vec<String> = issue.labels.iter().map(|l| l.name.clone()).collect();
+    #[tokio::test]
+    async fn test_every_tool_has_a_toolset_entry() {
+        let server = make_server(None, 30).with_writes(true);
+        let names = tool_names(&server);
+        assert_eq!(names.len(), toolsets::TOOLS.len());
+        for name in names {
+            assert!(toolsets::toolset_of(&name).is_some(), "{}", name);
+        }
+    }
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "number": issue.number,
-            "title": issue.title,
-            "state": format_state(&issue.state),
-            "author": issue.user.login,
-            "labels": labels,
-            "body": issue.body.as_deref().unwrap_or(""),
-            "comments": comment_items,
-            "created_at": issue.created_at.to_string(),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+    #[tokio::test]
+    async fn test_with_toolsets_filters_tools() {
+        let server = make_server(None, 30)
+            .with_toolsets(vec![Toolset::Pulls], vec!["list_pull_files".to_string()])
+            .with_writes(true);
+        assert_eq!(
+            tool_names(&server),
+            vec![
+                "get_pull",
+                "get_rate_limit",
+                "list_pull_review_comments",
+                "list_pull_reviews",
+                "list_pulls",
+            ]
+        );
+        let text = server.get_info().instructions.unwrap();
+        assert!(text.contains("list_pulls"), "{}", text);
+        assert!(!text.contains("list_pull_files"), "{}", text);
+        assert!(!text.contains("create_issue"), "{}", text);
+        assert!(!text.contains("Prompts:"), "{}", text);
     }
 
-    #[tool(
-        name = "list_pulls",
-        description = "List pull requests in a repository"
-    )]
-    async fn list_pulls(
-        &self,
-        Parameters(params): Parameters<ListPullsParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
+    #[tokio::test]
+    async fn test_prompts_follow_their_tools() {
+        let prompt_names = |server: &McpGithubServer| {
+            let mut names: Vec<String> = server
+                .prompt_router
+                .list_all()
+                .into_iter()
+                .map(|p| p.name)
+                .collect();
+            names.sort();
+            names
+        };
+        let server = make_server(None, 30).with_toolsets(
+            vec![Toolset::Pulls, Toolset::Issues, Toolset::Actions],
+            vec!["list_run_jobs".to_string()],
+        );
+        assert_eq!(
+            prompt_names(&server),
+            vec!["review_pull_request", "triage_issue"]
+        );
+        let text = server.get_info().instructions.unwrap();
+        assert!(
+            text.contains("Prompts: review_pull_request, triage_issue."),
+            "{}",
+            text
+        );
 
-        let per_page = self.capped_per_page(params.per_page);
+        let server = make_server(None, 30).with_toolsets(Toolset::ALL.to_vec(), Vec::new());
+        assert_eq!(prompt_names(&server).len(), toolsets::PROMPTS.len());
+    }
 
-        let pulls_handler = self.github.pulls(&owner, &params.repo);
-        let mut request = pulls_handler.list().per_page(per_page);
+    #[tokio::test]
+    async fn test_resolve_owner_with_param() {
+        let server = make_server(None, 30);
+        let result = server.resolve_owner(Some("my-org"));
+        assert_eq!(result.unwrap(), "my-org");
+    }
 
-        if let Some(ref state) = params.state {
-            request = match state.as_str() {
-                "open" => request.state(octocrab::params::State::Open),
-                "closed" => request.state(octocrab::params::State::Closed),
-                "all" => request.state(octocrab::params::State::All),
-                _ => request,
-            };
-        }
+    #[tokio::test]
+    async fn test_resolve_owner_with_default() {
+        let server = make_server(Some("default-org".to_string()), 30);
+        let result = server.resolve_owner(None);
+        assert_eq!(result.unwrap(), "default-org");
+    }
 
-        let pulls = request
-            .send()
-            .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+    #[tokio::test]
+    async fn test_resolve_owner_param_overrides_default() {
+        let server = make_server(Some("default-org".to_string()), 30);
+        let result = server.resolve_owner(Some("explicit-org"));
+        assert_eq!(result.unwrap(), "explicit-org");
+    }
 
-        let results: Vec<serde_json::Value> = pulls
-            .items
-            .iter()
-            .map(|p| {
-                serde_json::json!({
-                    "number": p.number,
-                    "title": p.title.as_deref().unwrap_or(""),
-                    "state": p.state.as_ref().map(format_state).unwrap_or("unknown"),
-                    "author": p.user.as_ref().map(|u| u.login.as_str()).unwrap_or("unknown"),
-                    "head": p.head.ref_field,
-                    "base": p.base.ref_field,
-                    "draft": p.draft,
-                    "created_at": p.created_at.map(|t| t.to_string()),
-                })
-            })
-            .collect();
+    #[tokio::test]
+    async fn test_resolve_owner_missing() {
+        let server = make_server(None, 30);
+        let result = server.resolve_owner(None);
+        assert!(result.is_err());
+    }
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "repo": format!("{}/{}", owner, params.repo),
-            "pulls": results,
-            "count": results.len(),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+    #[tokio::test]
+    async fn test_capped_per_page_default() {
+        let server = make_server(None, 30);
+        assert_eq!(server.capped_per_page(None), 30);
     }
 
-    #[tool(
-        name = "get_pull",
-        description = "Get pull request details including review summary and changed files count"
-    )]
-    async fn get_pull(
-        &self,
-        Parameters(params): Parameters<PullParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
+    #[tokio::test]
+    async fn test_capped_per_page_explicit() {
+        let server = make_server(None, 30);
+        assert_eq!(server.capped_per_page(Some(50)), 50);
+    }
 
-        let pr = self
-            .github
-            .pulls(&owner, &params.repo)
-            .get(params.pr_number)
-            .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+    #[tokio::test]
+    async fn test_capped_per_page_caps_at_100() {
+        let server = make_server(None, 30);
+        assert_eq!(server.capped_per_page(Some(200)), 100);
+        assert_eq!(server.capped_per_page(Some(1000)), 100);
+    }
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "number": pr.number,
-            "title": pr.title.as_deref().unwrap_or(""),
-            "state": pr.state.as_ref().map(format_state).unwrap_or("unknown"),
-            "author": pr.user.as_ref().map(|u| u.login.as_str()).unwrap_or("unknown"),
-            "body": pr.body.as_deref().unwrap_or(""),
-            "head": pr.head.ref_field,
-            "base": pr.base.ref_field,
-            "draft": pr.draft,
-            "mergeable": pr.mergeable,
-            "additions": pr.additions,
-            "deletions": pr.deletions,
-            "changed_files": pr.changed_files,
-            "commits": pr.commits,
-            "created_at": pr.created_at.map(|t| t.to_string()),
-            "merged_at": pr.merged_at.map(|t| t.to_string()),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+    #[tokio::test]
+    async fn test_capped_per_page_max_results_capped() {
+        // Even if max_results is set high, it should be capped at 100
+        let server = make_server(None, 500);
+        assert_eq!(server.capped_per_page(None), 100);
     }
 
-    #[tool(
-        name = "search_code",
-        description = "Search code across GitHub repositories using GitHub's code search syntax"
-    )]
-    async fn search_code(
-        &self,
-        Parameters(params): Parameters<SearchCodeParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let mut query = params.query.clone();
+    #[tokio::test]
+    async fn test_page_request_uses_limit_as_page_size() {
+        let server = make_server(None, 30);
+        let limit = |n| PageParams {
+            limit: Some(n),
+            ..PageParams::default()
+        };
+        let req = server.page_request(None, &limit(250)).unwrap();
+        assert_eq!(req.per_page, 100);
+        assert_eq!(req.limit, 250);
 
-        // Scope to owner/repo if specified
-        if let Some(ref owner) = params.owner.as_ref().or(self.default_owner.as_ref()) {
-            if let Some(ref repo) = params.repo {
-                query = format!("{} repo:{}/{}", query, owner, repo);
-            } else {
-                query = format!("{} org:{}", query, owner);
-            }
+        let req = server.page_request(None, &limit(10)).unwrap();
+        assert_eq!(req.per_page, 10);
+
+        let req = server.page_request(None, &PageParams::default()).unwrap();
+        assert_eq!(req.per_page, 30);
+        assert_eq!(req.limit, 30);
+
+        let req = server.page_request(Some(0), &PageParams::default()).unwrap();
+        assert_eq!(req.per_page, 1);
+        assert_eq!(req.limit, 1);
+    }
+
+    #[tokio::test]
+    async fn test_page_params_are_top_level_arguments() {
+        let params: RepoPageParams = serde_json::from_value(serde_json::json!({
+            "repo": "r", "page": 2, "cursor": "c", "limit": 50
+        }))
+        .unwrap();
+        assert_eq!(params.paging.page, Some(2));
+        assert_eq!(params.paging.cursor.as_deref(), Some("c"));
+        assert_eq!(params.paging.limit, Some(50));
+
+        let server = make_server(None, 30);
+        let tool = server.tool_router.get("list_branches").unwrap();
+        let properties = tool.input_schema.get("properties").unwrap();
+        for name in ["per_page", "page", "cursor", "limit"] {
+            assert!(properties.get(name).is_some(), "{}", name);
         }
+    }
 
-        let per_page = self.capped_per_page(params.per_page);
+    #[tokio::test]
+    async fn test_scoped_query() {
+        let server = make_server(Some("acme".to_string()), 30);
+        let repo = "api".to_string();
+        let other = "other".to_string();
+        let scoped = |owner, repo| server.scoped_query("bug", owner, repo).unwrap();
+        assert_eq!(scoped(None, None), "bug org:acme");
+        assert_eq!(scoped(None, Some(&repo)), "bug repo:acme/api");
+        assert_eq!(scoped(Some(&other), None), "bug org:other");
+        let unscoped = make_server(None, 30).scoped_query("bug", None, None);
+        assert_eq!(unscoped.unwrap(), "bug");
+    }
 
-        let results = self
-            .github
-            .search()
-            .code(&query)
-            .per_page(per_page)
-            .send()
-            .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+    #[tokio::test]
+    async fn test_write_tools_hidden_by_default() {
+        let server = make_server(None, 30);
+        assert!(!server.tool_router.has_route("create_issue"));
+        assert!(!server.tool_router.has_route("update_issue"));
+        assert!(!server.tool_router.has_route("add_issue_comment"));
+        assert!(server.tool_router.has_route("get_issue"));
+    }
 
-        let items: Vec<serde_json::Value> = results
-            .items
-            .iter()
-            .map(|item| {
-                serde_json::json!({
-                    "name": item.name,
-                    "path": item.path,
-                    "repository": item.repository.full_name.as_deref().unwrap_or(""),
-                    "url": item.html_url,
-                })
-            })
-            .collect();
+    #[tokio::test]
+    async fn test_write_tools_registered_with_writes() {
+        let server = make_server(None, 30).with_writes(true);
+        assert!(server.tool_router.has_route("create_issue"));
+        assert!(server.tool_router.has_route("update_issue"));
+        assert!(server.tool_router.has_route("add_issue_comment"));
+    }
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "query": params.query,
-            "results": items,
-            "count": items.len(),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+    fn server_for(mock: &MockServer) -> McpGithubServer {
+        let github = octocrab::Octocrab::builder()
+            .base_uri(mock.uri())
+            .unwrap()
+            .build()
+            .unwrap();
+        McpGithubServer::new(github, None, 30)
     }
 
-    #[tool(
-        name = "list_actions_runs",
-        description = "List recent GitHub Actions workflow runs for a repository"
-    )]
-    async fn list_actions_runs(
-        &self,
-        Parameters(params): Parameters<ActionsParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
+    #[tokio::test]
+    async fn test_http_errors_map_to_actionable_variants() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/issues/5"))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_json(serde_json::json!({"message": "Not Found"})),
+            )
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/private"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("x-accepted-oauth-scopes", "repo")
+                    .insert_header("x-oauth-scopes", "public_repo")
+                    .set_body_json(serde_json::json!({"message": "Forbidden"})),
+            )
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let err = server
+            .get_issue(Parameters(IssueParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                issue_number: 5,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+        assert!(err.message.contains("issue o/r#5"), "{}", err.message);
+
+        let err = server
+            .get_repo(Parameters(RepoParams {
+                owner: Some("o".to_string()),
+                repo: "private".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+        let data = err.data.unwrap();
+        assert_eq!(data["code"], "insufficient_scope");
+        assert_eq!(data["needed_scopes"], serde_json::json!(["repo"]));
+    }
 
-        // Validate owner and repo to prevent URL injection in raw route
-        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
-        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+    #[tokio::test]
+    async fn test_write_tools_name_missing_scopes() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/repos/o/r/issues"))
+            .and(wiremock::matchers::body_json(serde_json::json!({
+                "title": "Bug",
+                "labels": ["bug"],
+            })))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("x-accepted-oauth-scopes", "repo")
+                    .insert_header("x-oauth-scopes", "read:org")
+                    .set_body_json(serde_json::json!({
+                        "message": "Resource not accessible by personal access token"
+                    })),
+            )
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock).with_writes(true);
+
+        let err = server
+            .create_issue(Parameters(CreateIssueParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                title: "Bug".to_string(),
+                body: None,
+                assignees: None,
+                labels: Some(vec!["bug".to_string()]),
+                milestone: None,
+            }))
+            .await
+            .unwrap_err();
+        let data = err.data.unwrap();
+        assert_eq!(data["code"], "insufficient_scope");
+        assert_eq!(data["needed_scopes"], serde_json::json!(["repo"]));
+    }
 
-        let per_page = self.capped_per_page(params.per_page);
+    #[tokio::test]
+    async fn test_writes_invalidate_cached_reads() {
+        let mock = MockServer::start().await;
+        let issue = |title: &str| {
+            ResponseTemplate::new(200)
+                .insert_header("etag", format!("\"{}\"", title))
+                .set_body_json(serde_json::json!({"number": 5, "title": title}))
+        };
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/issues/5"))
+            .respond_with(issue("old"))
+            .up_to_n_times(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/issues/5"))
+            .respond_with(issue("new"))
+            .mount(&mock)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/repos/o/r/issues/5"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"title": "new"})),
+            )
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock)
+            .with_cache(ResponseCache::new(std::time::Duration::from_secs(300), None));
+        let read = || server.api_get::<serde_json::Value>("/repos/o/r/issues/5");
+
+        assert_eq!(read().await.unwrap()["title"], "old");
+        assert_eq!(read().await.unwrap()["title"], "old");
+        let _: serde_json::Value = server
+            .api_send(
+                http::Method::PATCH,
+                "/repos/o/r/issues/5",
+                &serde_json::json!({"title": "new"}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(read().await.unwrap()["title"], "new");
+        assert_eq!(server.cache.stats().hits, 1);
 
-        let route = format!(
-            "/repos/{}/{}/actions/runs?per_page={}",
-            owner, params.repo, per_page
+        assert_eq!(
+            written_collection("/repos/o/r/issues/5/comments"),
+            "/repos/o/r/issues"
         );
+        assert_eq!(written_collection("/repos/o/r/issues"), "/repos/o/r/issues");
+    }
 
-        let response: serde_json::Value = self
-            .github
-            .get(route, None::<&()>)
-            .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+    #[tokio::test]
+    async fn test_recoverable_errors_become_tool_results() {
+        let policy = RepoPolicy::new(&[], &["o/r".to_string()]).unwrap();
+        let server = make_server(None, 30).with_repo_policy(policy);
+        let get_repo = |repo: &str| {
+            server.get_repo(Parameters(RepoParams {
+                owner: Some("o".to_string()),
+                repo: repo.to_string(),
+            }))
+        };
 
-        let runs = response
-            .get("workflow_runs")
-            .and_then(|r| r.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .map(|run| {
-                        serde_json::json!({
-                            "id": run.get("id"),
-                            "name": run.get("name"),
-                            "status": run.get("status"),
-                            "conclusion": run.get("conclusion"),
-                            "branch": run.get("head_branch"),
-                            "event": run.get("event"),
-                            "created_at": run.get("created_at"),
-                        })
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default();
+        let forbidden = get_repo("r").await.unwrap_err();
+        let result = tool_error_result(forbidden).unwrap();
+        assert_eq!(result.is_error, Some(true));
+        let text = &result.content[0].as_text().unwrap().text;
+        let body: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(body["code"], "forbidden");
+        assert!(body["message"].as_str().unwrap().contains("o/r"));
+        assert!(body["hint"].is_string());
+        assert!(body.get("docs_url").is_some());
+
+        // Malformed arguments stay protocol errors.
+        let malformed = get_repo("bad repo").await.unwrap_err();
+        assert_eq!(
+            tool_error_result(malformed).unwrap_err().code,
+            ErrorCode::INVALID_PARAMS
+        );
+    }
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "repo": format!("{}/{}", owner, params.repo),
-            "runs": runs,
-            "count": runs.len(),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+    #[tokio::test]
+    async fn test_get_rate_limit_classifies_bad_credentials() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rate_limit"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "message": "Bad credentials"
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let err = server.get_rate_limit().await.unwrap_err();
+        let result = tool_error_result(err).unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        let body: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(body["code"], "unauthenticated");
     }
 
-    #[tool(
-        name = "list_commits",
-        description = "List commits on a branch or tag"
-    )]
-    async fn list_commits(
-        &self,
-        Parameters(params): Parameters<ListCommitsParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
-        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
-        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+    #[tokio::test]
+    async fn test_primary_rate_limit_reports_reset_time() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/issues"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "message": "API rate limit exceeded for user ID 1."
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/rate_limit"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "resources": {"core": {"limit": 5000, "remaining": 0, "reset": 1700000000}}
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
 
-        let per_page = self.capped_per_page(params.per_page);
-        let mut route = format!(
-            "/repos/{}/{}/commits?per_page={}",
-            owner, params.repo, per_page
-        );
-        if let Some(ref sha) = params.sha {
-            sanitize_url_value(sha, "sha").map_err(|e| self.err(e))?;
-            route.push_str(&format!("&sha={}", sha));
+        let issues = &server.github.issues("o", "r");
+        let result = server
+            .call_github("repository o/r", || issues.list().send())
+            .await;
+        match result {
+            Err(McpGithubError::RateLimited { reset_at }) => {
+                assert_eq!(reset_at.timestamp(), 1_700_000_000)
+            }
+            other => panic!("unexpected: {:?}", other.map(|_| ())),
         }
-        if let Some(ref author) = params.author {
-            sanitize_url_value(author, "author").map_err(|e| self.err(e))?;
-            route.push_str(&format!("&author={}", author));
+    }
+
+    #[tokio::test]
+    async fn test_read_resource_decodes_text_and_binary() {
+        use base64::Engine;
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/contents/docs/README.md"))
+            .and(query_param("ref", "feature/x"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "path": "docs/README.md",
+                "content": format!("{}\n", b64.encode("# Hello")),
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/contents/logo.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "path": "logo.png",
+                "content": b64.encode([0x89, 0xff, 0x00]),
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let uri = "github://o/r/blob/feature%2Fx/docs/README.md";
+        let result = server.read_repo_resource(uri).await.unwrap();
+        match &result.contents[0] {
+            ResourceContents::TextResourceContents {
+                text, mime_type, ..
+            } => {
+                assert_eq!(text, "# Hello");
+                assert_eq!(mime_type.as_deref(), Some("text/markdown"));
+            }
+            other => panic!("unexpected: {:?}", other),
         }
 
-        let response: Vec<serde_json::Value> = self
-            .github
-            .get(&route, None::<&()>)
+        let result = server
+            .read_repo_resource("github://o/r/blob/main/logo.png")
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
-
-        let commits: Vec<serde_json::Value> = response
-            .iter()
-            .map(|c| {
-                serde_json::json!({
-                    "sha": c.get("sha"),
-                    "message": c.pointer("/commit/message"),
-                    "author": c.pointer("/commit/author/name"),
-                    "author_login": c.pointer("/author/login"),
-                    "date": c.pointer("/commit/author/date"),
-                })
-            })
-            .collect();
+            .unwrap();
+        assert!(matches!(
+            &result.contents[0],
+            ResourceContents::BlobResourceContents { mime_type: Some(m), .. } if m == "image/png"
+        ));
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "repo": format!("{}/{}", owner, params.repo),
-            "commits": commits,
-            "count": commits.len(),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+        assert!(server.read_repo_resource("github://o/r").await.is_err());
     }
 
-    #[tool(
-        name = "get_commit",
-        description = "Get full commit details including changed files"
-    )]
-    async fn get_commit(
-        &self,
-        Parameters(params): Parameters<CommitRefParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
-        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
-        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
-        sanitize_url_value(&params.sha, "ref").map_err(|e| self.err(e))?;
-
-        let route = format!(
-            "/repos/{}/{}/commits/{}",
-            owner, params.repo, params.sha
-        );
+    #[tokio::test]
+    async fn test_read_resource_encodes_path() {
+        use base64::Engine;
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/contents/docs/my%20file.md"))
+            .and(query_param("ref", "main"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "path": "docs/my file.md",
+                "content": b64.encode("spaced"),
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
 
-        let c: serde_json::Value = self
-            .github
-            .get(&route, None::<&()>)
+        let result = server
+            .read_repo_resource("github://o/r/blob/main/docs/my%20file.md")
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+            .unwrap();
+        match &result.contents[0] {
+            ResourceContents::TextResourceContents { text, .. } => assert_eq!(text, "spaced"),
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
 
-        let files = c
-            .get("files")
-            .and_then(|f| f.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .map(|f| {
-                        serde_json::json!({
-                            "filename": f.get("filename"),
-                            "status": f.get("status"),
-                            "additions": f.get("additions"),
-                            "deletions": f.get("deletions"),
-                            "changes": f.get("changes"),
-                        })
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default();
+    #[tokio::test]
+    async fn test_list_resources_pages_repo_tree() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "default_branch": "main"
+            })))
+            .mount(&mock)
+            .await;
+        let mut tree = vec![serde_json::json!({"path": "src", "type": "tree", "sha": "t"})];
+        for i in 0..RESOURCES_PAGE_SIZE + 5 {
+            tree.push(serde_json::json!({
+                "path": format!("src/f{}.rs", i), "type": "blob", "size": 10, "sha": "b"
+            }));
+        }
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/git/trees/main"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "tree": tree, "truncated": false
+            })))
+            .mount(&mock)
+            .await;
 
-        let file_count = files.len();
+        let unconfigured = server_for(&mock);
+        assert!(unconfigured
+            .list_repo_resources(None)
+            .await
+            .unwrap()
+            .resources
+            .is_empty());
+
+        let server = server_for(&mock).with_resource_repo("o".to_string(), "r".to_string());
+        let first = server.list_repo_resources(None).await.unwrap();
+        assert_eq!(first.resources.len(), RESOURCES_PAGE_SIZE);
+        assert_eq!(first.resources[0].uri, "github://o/r/blob/main/src/f0.rs");
+        let cursor = first.next_cursor.expect("more pages");
+
+        let second = server.list_repo_resources(Some(&cursor)).await.unwrap();
+        assert_eq!(second.resources.len(), 5);
+        assert!(second.next_cursor.is_none());
+    }
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "sha": c.get("sha"),
-            "message": c.pointer("/commit/message"),
-            "author": c.pointer("/commit/author/name"),
-            "author_login": c.pointer("/author/login"),
-            "date": c.pointer("/commit/author/date"),
-            "parents": c.get("parents").and_then(|p| p.as_array()).map(|arr| {
-                arr.iter().filter_map(|p| p.get("sha")).collect::<Vec<_>>()
-            }),
-            "stats": c.get("stats"),
-            "files": files,
-            "file_count": file_count,
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+    #[tokio::test]
+    async fn test_prompt_catalog() {
+        let server = make_server(None, 30);
+        let mut names: Vec<String> = server
+            .prompt_router
+            .list_all()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "draft_release_notes",
+                "review_pull_request",
+                "summarize_ci_failure",
+                "triage_issue"
+            ]
+        );
     }
 
-    #[tool(
-        name = "list_branches",
-        description = "List branches in a repository"
-    )]
-    async fn list_branches(
-        &self,
-        Parameters(params): Parameters<RepoPageParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
-        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
-        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+    #[test]
+    fn test_prompt_args_accept_string_numbers() {
+        let args: PullPromptArgs =
+            serde_json::from_value(serde_json::json!({"repo": "r", "number": "#42"})).unwrap();
+        assert_eq!(args.number, 42);
+        let args: PullPromptArgs =
+            serde_json::from_value(serde_json::json!({"repo": "r", "number": 7})).unwrap();
+        assert_eq!(args.number, 7);
+        assert!(serde_json::from_value::<PullPromptArgs>(
+            serde_json::json!({"repo": "r", "number": "abc"})
+        )
+        .is_err());
+
+        let args: CiFailurePromptArgs =
+            serde_json::from_value(serde_json::json!({"repo": "r", "number": ""})).unwrap();
+        assert_eq!(args.number, None);
+    }
 
-        let per_page = self.capped_per_page(params.per_page);
-        let route = format!(
-            "/repos/{}/{}/branches?per_page={}",
-            owner, params.repo, per_page
-        );
+    #[tokio::test]
+    async fn test_draft_release_notes_lists_commits_since_latest_release() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/releases/latest"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "tag_name": "v1.2.0",
+                "name": "1.2.0",
+                "published_at": "2024-05-01T12:00:00Z",
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits"))
+            .and(query_param("since", "2024-05-01T12:00:00Z"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                "sha": "abc123",
+                "commit": {"message": "Add widgets", "author": {"name": "A"}},
+                "author": {"login": "alice"},
+            }])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .draft_release_notes_prompt(Parameters(ReleaseNotesPromptArgs {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                sha: None,
+            }))
+            .await
+            .unwrap();
+        let PromptMessageContent::Text { text } = &result.messages[0].content else {
+            panic!("expected a text message");
+        };
+        assert!(text.contains("v1.2.0"), "{}", text);
+        assert!(text.contains("Add widgets"), "{}", text);
+
+        // A repository without releases gets its recent history instead.
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/releases/latest"))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_json(serde_json::json!({"message": "Not Found"})),
+            )
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let result = server_for(&mock)
+            .draft_release_notes_prompt(Parameters(ReleaseNotesPromptArgs {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                sha: None,
+            }))
+            .await
+            .unwrap();
+        let PromptMessageContent::Text { text } = &result.messages[0].content else {
+            panic!("expected a text message");
+        };
+        assert!(text.contains("Latest release:\n```json\nnull"), "{}", text);
+    }
 
-        let response: Vec<serde_json::Value> = self
-            .github
-            .get(&route, None::<&()>)
+    #[tokio::test]
+    async fn test_summarize_ci_failure_embeds_latest_failed_run() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/actions/runs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "workflow_runs": [
+                    {"id": 3, "name": "CI", "status": "in_progress", "conclusion": null},
+                    {"id": 2, "name": "CI", "status": "completed", "conclusion": "failure"},
+                    {"id": 1, "name": "CI", "status": "completed", "conclusion": "failure"},
+                ]
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/actions/runs/2/jobs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 1,
+                "jobs": [{"id": 99, "name": "test-linux", "conclusion": "failure", "steps": []}]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .summarize_ci_failure_prompt(Parameters(CiFailurePromptArgs {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                number: None,
+            }))
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+            .unwrap();
+        assert_eq!(result.messages.len(), 1);
+        let PromptMessageContent::Text { text } = &result.messages[0].content else {
+            panic!("expected a text message");
+        };
+        assert!(text.contains("workflow run 2 in o/r"));
+        assert!(text.contains("test-linux"));
+    }
 
-        let branches: Vec<serde_json::Value> = response
-            .iter()
-            .map(|b| {
-                serde_json::json!({
-                    "name": b.get("name"),
-                    "sha": b.pointer("/commit/sha"),
-                    "protected": b.get("protected"),
-                })
-            })
-            .collect();
+    #[tokio::test]
+    async fn test_search_issues_scopes_query_and_summarizes() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search/issues"))
+            .and(query_param("q", "timeout label:bug repo:o/r is:issue"))
+            .and(query_param("sort", "updated"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 1,
+                "incomplete_results": false,
+                "items": [{
+                    "number": 12,
+                    "title": "Request timeout",
+                    "state": "open",
+                    "repository_url": "https://api.github.com/repos/o/r",
+                    "labels": [{"name": "bug"}],
+                    "score": 1.5,
+                }]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .search_issues(Parameters(SearchIssuesParams {
+                query: "timeout label:bug".to_string(),
+                owner: Some("o".to_string()),
+                repo: Some("r".to_string()),
+                kind: Some("issue".to_string()),
+                sort: Some("updated".to_string()),
+                order: None,
+                per_page: None,
+            }))
+            .await
+            .unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        let body: serde_json::Value = serde_json::from_str(text).unwrap();
+        let item = &body["results"][0];
+        assert_eq!(item["number"], 12);
+        assert_eq!(item["repo"], "o/r");
+        assert_eq!(item["type"], "issue");
+        assert_eq!(item["labels"], serde_json::json!(["bug"]));
+        assert_eq!(item["score"], 1.5);
+
+        let bad_sort = server
+            .search_issues(Parameters(SearchIssuesParams {
+                query: "x".to_string(),
+                owner: None,
+                repo: None,
+                kind: None,
+                sort: Some("stars".to_string()),
+                order: None,
+                per_page: None,
+            }))
+            .await;
+        assert!(bad_sort.is_err());
+    }
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "repo": format!("{}/{}", owner, params.repo),
-            "branches": branches,
-            "count": branches.len(),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+    fn restricted(server: McpGithubServer) -> McpGithubServer {
+        let policy = RepoPolicy::new(&["o/*".to_string()], &["o/secret".to_string()]).unwrap();
+        server.with_repo_policy(policy)
     }
 
-    #[tool(
-        name = "get_file_contents",
-        description = "Get file content from a repository at a specific ref"
-    )]
-    async fn get_file_contents(
-        &self,
-        Parameters(params): Parameters<FileContentsParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
-        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
-        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
-        sanitize_url_value(&params.path, "path").map_err(|e| self.err(e))?;
+    #[tokio::test]
+    async fn test_repo_policy_blocks_tools_before_calling_github() {
+        let mock = MockServer::start().await;
+        let server = restricted(server_for(&mock));
+
+        for (owner, repo) in [("o", "secret"), ("other", "r")] {
+            let err = server
+                .get_repo(Parameters(RepoParams {
+                    owner: Some(owner.to_string()),
+                    repo: repo.to_string(),
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+            assert!(err.message.contains("Forbidden"), "{}", err.message);
+        }
+        let err = server
+            .graphql_query(Parameters(GraphqlParams {
+                query: "{ viewer { login } }".to_string(),
+                variables: None,
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("graphql_query"), "{}", err.message);
+        let err = server
+            .read_repo_resource("github://o/secret/blob/main/README.md")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpGithubError::Forbidden(_)));
+        assert!(mock.received_requests().await.unwrap().is_empty());
+    }
 
-        let mut route = format!(
-            "/repos/{}/{}/contents/{}",
-            owner, params.repo, params.path
-        );
-        if let Some(ref git_ref) = params.git_ref {
-            sanitize_url_value(git_ref, "ref").map_err(|e| self.err(e))?;
-            route.push_str(&format!("?ref={}", git_ref));
+    #[tokio::test]
+    async fn test_repo_policy_filters_search_results() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search/issues"))
+            .and(query_param("q", "leak"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 3,
+                "incomplete_results": false,
+                "items": [
+                    {"number": 1, "repository_url": "https://api.github.com/repos/o/r"},
+                    {"number": 2, "repository_url": "https://api.github.com/repos/o/secret"},
+                    {"number": 3, "repository_url": "https://api.github.com/repos/x/y"},
+                ]
+            })))
+            .mount(&mock)
+            .await;
+        let server = restricted(server_for(&mock));
+
+        let result = server
+            .search_issues(Parameters(SearchIssuesParams {
+                query: "leak".to_string(),
+                owner: None,
+                repo: None,
+                kind: None,
+                sort: None,
+                order: None,
+                per_page: None,
+            }))
+            .await
+            .unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        let body: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(body["count"], 1);
+        assert_eq!(body["results"][0]["repo"], "o/r");
+        // GitHub's total also counts the hidden repositories
+        assert!(body.get("total_count").is_none(), "{}", body);
+
+        let scoped = server
+            .search_issues(Parameters(SearchIssuesParams {
+                query: "leak".to_string(),
+                owner: Some("o".to_string()),
+                repo: Some("secret".to_string()),
+                kind: None,
+                sort: None,
+                order: None,
+                per_page: None,
+            }))
+            .await;
+        assert!(scoped.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_repo_policy_checks_search_qualifiers() {
+        let mock = MockServer::start().await;
+        let server = restricted(server_for(&mock));
+
+        for query in [
+            "AKIA repo:o/secret",
+            "AKIA (repo:O/Secret OR repo:o/r)",
+            "AKIA REPO:\"o/secret\"",
+            "AKIA org:other",
+            "AKIA user:other",
+        ] {
+            let result = server
+                .search_code(Parameters(SearchCodeParams {
+                    query: query.to_string(),
+                    owner: None,
+                    repo: None,
+                    per_page: None,
+                }))
+                .await;
+            let err = result.unwrap_err();
+            assert!(err.message.contains("not allowed"), "{}: {}", query, err.message);
         }
+        let org = server.scoped_query("AKIA", Some(&"other".to_string()), None);
+        assert!(matches!(org, Err(McpGithubError::Forbidden(_))));
+
+        assert!(server
+            .scoped_query("AKIA repo:o/r -repo:o/secret org:o", None, None)
+            .is_ok());
+        assert!(mock.received_requests().await.unwrap().is_empty());
+    }
 
-        let response: serde_json::Value = self
-            .github
-            .get(&route, None::<&()>)
+    #[tokio::test]
+    async fn test_search_commits_uses_default_owner_and_users_are_unscoped() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search/commits"))
+            .and(query_param("q", "fix timeout org:acme"))
+            .and(query_param("order", "asc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 1,
+                "items": [{
+                    "sha": "abc123",
+                    "repository": {"full_name": "acme/api"},
+                    "commit": {"message": "Fix timeout", "author": {"name": "Jane"}},
+                }]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/search/users"))
+            .and(query_param("q", "jane"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 0, "items": []
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let github = octocrab::Octocrab::builder()
+            .base_uri(mock.uri())
+            .unwrap()
+            .build()
+            .unwrap();
+        let server = McpGithubServer::new(github, Some("acme".to_string()), 30);
+
+        let result = server
+            .search_commits(Parameters(SearchCommitsParams {
+                query: "fix timeout".to_string(),
+                owner: None,
+                repo: None,
+                sort: None,
+                order: Some("asc".to_string()),
+                per_page: None,
+            }))
+            .await
+            .unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        let body: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(body["results"][0]["repo"], "acme/api");
+        assert_eq!(body["results"][0]["message"], "Fix timeout");
+
+        server
+            .search_users(Parameters(SearchUsersParams {
+                query: "jane".to_string(),
+                sort: None,
+                order: None,
+                per_page: None,
+            }))
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+            .unwrap();
+
+        let bad_order = server
+            .search_users(Parameters(SearchUsersParams {
+                query: "jane".to_string(),
+                sort: None,
+                order: Some("up".to_string()),
+                per_page: None,
+            }))
+            .await;
+        assert!(bad_order.is_err());
+    }
 
-        // Decode base64 content (GitHub returns base64 with embedded newlines)
-        let content = response
-            .get("content")
-            .and_then(|c| c.as_str())
-            .map(|c| {
-                let cleaned: String = c.chars().filter(|ch| !ch.is_whitespace()).collect();
-                use base64::Engine;
-                base64::engine::general_purpose::STANDARD
-                    .decode(&cleaned)
-                    .ok()
-                    .and_then(|bytes| String::from_utf8(bytes).ok())
-                    .unwrap_or_else(|| "[binary content]".to_string())
+    #[test]
+    fn test_group_blame_by_commit_with_line_filter() {
+        fn range(first: u64, last: u64, sha: &str) -> serde_json::Value {
+            serde_json::json!({
+                "startingLine": first,
+                "endingLine": last,
+                "commit": {"oid": sha, "messageHeadline": format!("commit {}", sha)},
             })
-            .unwrap_or_default();
+        }
+        let ranges = [
+            range(1, 3, "a"),
+            range(4, 10, "b"),
+            range(11, 12, "a"),
+            range(13, 20, "c"),
+        ];
+
+        let all = group_blame(&ranges, 1, u64::MAX);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0]["sha"], "a");
+        assert_eq!(all[0]["lines"], 5);
+        assert_eq!(all[0]["ranges"], serde_json::json!([[1, 3], [11, 12]]));
+
+        let window = group_blame(&ranges, 5, 11);
+        assert_eq!(window.len(), 2);
+        assert_eq!(window[0]["sha"], "b");
+        assert_eq!(window[0]["ranges"], serde_json::json!([[5, 10]]));
+        assert_eq!(window[1]["ranges"], serde_json::json!([[11, 11]]));
+    }
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "path": response.get("path"),
-            "name": response.get("name"),
-            "size": response.get("size"),
-            "encoding": response.get("encoding"),
-            "content": content,
-            "sha": response.get("sha"),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+    #[tokio::test]
+    async fn test_get_blame_unknown_ref() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {"repository": {"object": null}}
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .get_blame(Parameters(BlameParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                path: "src/lib.rs".to_string(),
+                git_ref: Some("nope".to_string()),
+                start_line: None,
+                end_line: None,
+            }))
+            .await;
+        assert!(result.unwrap_err().message.contains("commit nope in o/r"));
     }
 
-    #[tool(
-        name = "list_releases",
-        description = "List releases for a repository"
-    )]
-    async fn list_releases(
-        &self,
-        Parameters(params): Parameters<RepoPageParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
-        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
-        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
+    #[tokio::test]
+    async fn test_graphql_uses_enterprise_endpoint() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {"repository": {"object": null}}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let options = crate::client::ClientOptions {
+            api_url: Some(format!("{}/api/v3", mock.uri())),
+            ..Default::default()
+        };
+        let github = crate::client::build(&options).await.unwrap();
+        let server = McpGithubServer::new(github, None, 30).with_graphql_url(options.graphql_url());
+
+        let result = server
+            .get_blame(Parameters(BlameParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                path: "src/lib.rs".to_string(),
+                git_ref: Some("nope".to_string()),
+                start_line: None,
+                end_line: None,
+            }))
+            .await;
+        assert!(result.unwrap_err().message.contains("commit nope in o/r"));
+    }
 
-        let per_page = self.capped_per_page(params.per_page);
-        let route = format!(
-            "/repos/{}/{}/releases?per_page={}",
-            owner, params.repo, per_page
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("2024-05-01", "since").unwrap(),
+            "2024-05-01T00:00:00Z"
+        );
+        assert_eq!(
+            parse_timestamp("2024-05-01T14:30:00+02:00", "since").unwrap(),
+            "2024-05-01T12:30:00Z"
         );
+        assert!(parse_timestamp("last month", "since").is_err());
+    }
 
-        let response: Vec<serde_json::Value> = self
-            .github
-            .get(&route, None::<&()>)
+    #[tokio::test]
+    async fn test_list_commits_filters_and_merge_info() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits"))
+            .and(query_param("path", "src/auth"))
+            .and(query_param("since", "2024-05-01T00:00:00Z"))
+            .and(query_param("committer", "web-flow"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                    "sha": "m1",
+                    "commit": {
+                        "message": "Merge pull request #1",
+                        "verification": {"verified": true, "reason": "valid"},
+                    },
+                    "parents": [{"sha": "p1"}, {"sha": "p2"}],
+                }])),
+            )
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .list_commits(Parameters(ListCommitsParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                sha: None,
+                author: None,
+                committer: Some("web-flow".to_string()),
+                path: Some("src/auth".to_string()),
+                since: Some("2024-05-01".to_string()),
+                until: None,
+                per_page: None,
+                paging: PageParams::default(),
+            }))
             .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
-
-        let releases: Vec<serde_json::Value> = response
-            .iter()
-            .map(|r| {
-                let assets = r
-                    .get("assets")
-                    .and_then(|a| a.as_array())
-                    .map(|a| a.len())
-                    .unwrap_or(0);
-                serde_json::json!({
-                    "tag": r.get("tag_name"),
-                    "name": r.get("name"),
-                    "author": r.pointer("/author/login"),
-                    "prerelease": r.get("prerelease"),
-                    "draft": r.get("draft"),
-                    "published_at": r.get("published_at"),
-                    "asset_count": assets,
-                })
-            })
-            .collect();
-
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "repo": format!("{}/{}", owner, params.repo),
-            "releases": releases,
-            "count": releases.len(),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+            .unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(body["commits"][0]["verified"], true);
+        assert_eq!(body["commits"][0]["parent_count"], 2);
     }
 
-    #[tool(
-        name = "list_tags",
-        description = "List tags in a repository"
-    )]
-    async fn list_tags(
-        &self,
-        Parameters(params): Parameters<RepoPageParams>,
-    ) -> Result<CallToolResult, ErrorData> {
-        let owner = self
-            .resolve_owner(params.owner.as_deref())
-            .map_err(|e| self.err(e))?;
-        sanitize_github_name(&owner, "owner").map_err(|e| self.err(e))?;
-        sanitize_github_name(&params.repo, "repo").map_err(|e| self.err(e))?;
-
-        let per_page = self.capped_per_page(params.per_page);
-        let route = format!(
-            "/repos/{}/{}/tags?per_page={}",
-            owner, params.repo, per_page
+    #[test]
+    fn test_split_compare_refs() {
+        assert_eq!(
+            split_compare_refs("v1.0", Some("main")).unwrap(),
+            ("v1.0".to_string(), "main".to_string())
+        );
+        assert_eq!(
+            split_compare_refs("main...octocat:feature/x", None).unwrap(),
+            ("main".to_string(), "octocat:feature/x".to_string())
         );
+        assert!(split_compare_refs("main", Some("octocat:fork:topic")).is_ok());
+        assert!(split_compare_refs("main", None).is_err());
+        assert!(split_compare_refs("main...dev", Some("dev")).is_err());
+        assert!(split_compare_refs("main", Some("a..b")).is_err());
+        assert!(split_compare_refs("main", Some("bad owner:dev")).is_err());
+        assert!(split_compare_refs("main", Some("dev?x=1")).is_err());
+        assert!(split_compare_refs("main...", None).is_err());
+    }
 
-        let response: Vec<serde_json::Value> = self
-            .github
-            .get(&route, None::<&()>)
-            .await
-            .map_err(|e| self.err(McpGithubError::GitHub(e)))?;
+    #[tokio::test]
+    async fn test_compare_refs_summarizes_and_omits_patches_by_default() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/compare/v1.0...main"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "ahead",
+                "ahead_by": 2,
+                "behind_by": 0,
+                "total_commits": 2,
+                "merge_base_commit": {"sha": "base123"},
+                "commits": [{"sha": "c1", "commit": {"message": "One"}}, {"sha": "c2"}],
+                "files": [{"filename": "src/lib.rs", "status": "modified", "patch": "@@ -1 +1 @@"}],
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+        let params = |include_patch| CompareParams {
+            owner: Some("o".to_string()),
+            repo: "r".to_string(),
+            base: "v1.0...main".to_string(),
+            head: None,
+            include_patch,
+            max_patch_bytes: None,
+        };
 
-        let tags: Vec<serde_json::Value> = response
-            .iter()
-            .map(|t| {
-                serde_json::json!({
-                    "name": t.get("name"),
-                    "sha": t.pointer("/commit/sha"),
-                })
-            })
-            .collect();
+        let result = server.compare_refs(Parameters(params(None))).await.unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(body["ahead_by"], 2);
+        assert_eq!(body["merge_base"], "base123");
+        assert_eq!(body["commits"].as_array().unwrap().len(), 2);
+        assert!(body["files"][0].get("patch").is_none());
 
-        let text = serde_json::to_string_pretty(&serde_json::json!({
-            "repo": format!("{}/{}", owner, params.repo),
-            "tags": tags,
-            "count": tags.len(),
-        }))
-        .unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(text)]))
+        let result = server
+            .compare_refs(Parameters(params(Some(true))))
+            .await
+            .unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(body["files"][0]["patch"], "@@ -1 +1 @@");
     }
-}
 
-#[tool_handler]
-impl ServerHandler for McpGithubServer {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: Implementation {
-                name: "mcp-github".to_string(),
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                ..Default::default()
-            },
-            instructions: Some(
-                "GitHub server. Use list_repos to see repositories, get_repo for repo details, \
-                 list_issues/get_issue for issues, list_pulls/get_pull for PRs, \
-                 search_code to search code, list_actions_runs for CI/CD runs, \
-                 list_commits/get_commit for commit history, list_branches for branches, \
-                 get_file_contents to read files, list_releases for releases, \
-                 and list_tags for tags."
-                    .to_string(),
-            ),
+    #[test]
+    fn test_checks_verdict() {
+        fn check(status: &str, conclusion: Option<&str>) -> serde_json::Value {
+            serde_json::json!({"status": status, "conclusion": conclusion})
         }
+        assert_eq!(checks_verdict(&[]), "none");
+        assert_eq!(
+            checks_verdict(&[
+                check("completed", Some("success")),
+                check("completed", Some("skipped"))
+            ]),
+            "success"
+        );
+        assert_eq!(
+            checks_verdict(&[
+                check("completed", Some("success")),
+                check("in_progress", None)
+            ]),
+            "pending"
+        );
+        assert_eq!(
+            checks_verdict(&[
+                check("in_progress", None),
+                check("completed", Some("timed_out"))
+            ]),
+            "failure"
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_get_checks_merges_runs_suites_and_statuses() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits/main/check-runs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 1,
+                "check_runs": [{
+                    "name": "build", "status": "completed", "conclusion": "success",
+                    "app": {"slug": "github-actions"},
+                    "details_url": "https://example.com/build",
+                }]
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits/main/check-suites"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "check_suites": [
+                    {"status": "completed", "latest_check_runs_count": 1,
+                     "app": {"slug": "github-actions", "name": "GitHub Actions"}},
+                    {"status": "queued", "latest_check_runs_count": 0,
+                     "app": {"slug": "codecov", "name": "Codecov"}},
+                ]
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits/main/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "sha": "abc",
+                "state": "failure",
+                "statuses": [{"context": "ci/jenkins", "state": "error", "target_url": "https://ci"}]
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .get_checks(Parameters(ChecksParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                git_ref: Some("main".to_string()),
+                pr_number: None,
+            }))
+            .await
+            .unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        let body: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(body["count"], 3);
+        assert_eq!(body["verdict"], "failure");
+        assert_eq!(body["checks"][1]["name"], "Codecov");
+        assert_eq!(body["checks"][2]["conclusion"], "failure");
+        assert_eq!(body["incomplete"], false);
+
+        let neither = server
+            .get_checks(Parameters(ChecksParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                git_ref: None,
+                pr_number: None,
+            }))
+            .await;
+        assert!(neither.is_err());
+    }
 
-    fn make_server(default_owner: Option<String>, max_results: u32) -> McpGithubServer {
-        let github = octocrab::Octocrab::default();
-        McpGithubServer::new(github, default_owner, max_results)
+    #[tokio::test]
+    async fn test_get_checks_pages_through_suites_and_statuses() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits/main/check-runs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 0, "check_runs": []
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits/main/check-suites"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "check_suites": [{"status": "queued", "latest_check_runs_count": 0,
+                                  "app": {"slug": "codecov", "name": "Codecov"}}]
+            })))
+            .mount(&mock)
+            .await;
+        let completed = serde_json::json!({"status": "completed", "latest_check_runs_count": 1});
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits/main/check-suites"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "check_suites": vec![completed; 100]
+            })))
+            .mount(&mock)
+            .await;
+        let status = serde_json::json!({"context": "ci", "state": "success"});
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/commits/main/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "sha": "abc",
+                "statuses": vec![status; 100]
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .get_checks(Parameters(ChecksParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                git_ref: Some("main".to_string()),
+                pr_number: None,
+            }))
+            .await
+            .unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        let body: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(body["checks"][0]["name"], "Codecov");
+        assert_eq!(body["count"], 1 + 100 * MAX_CHECK_PAGES);
+        assert_eq!(body["sha"], "abc");
+        assert_eq!(body["incomplete"], true);
     }
 
-    // Note: Octocrab::default() requires a Tokio runtime (tower::Buffer),
-    // so these tests must be async even though they don't await anything.
+    #[test]
+    fn test_review_summary_latest_verdict_per_reviewer() {
+        fn review(login: &str, state: &str) -> serde_json::Value {
+            serde_json::json!({"user": {"login": login}, "state": state})
+        }
+        let summary = review_summary(&[
+            review("alice", "CHANGES_REQUESTED"),
+            review("alice", "COMMENTED"),
+            review("bob", "APPROVED"),
+            review("carol", "COMMENTED"),
+            review("dave", "APPROVED"),
+            review("dave", "DISMISSED"),
+            review("erin", "PENDING"),
+        ]);
+        assert_eq!(summary["decision"], "changes_requested");
+        assert_eq!(summary["changes_requested"], serde_json::json!(["alice"]));
+        assert_eq!(summary["approved"], serde_json::json!(["bob"]));
+        assert_eq!(summary["commented"], serde_json::json!(["carol"]));
+
+        let summary = review_summary(&[
+            review("alice", "CHANGES_REQUESTED"),
+            review("alice", "APPROVED"),
+        ]);
+        assert_eq!(summary["decision"], "approved");
+        assert_eq!(review_summary(&[])["decision"], "none");
+    }
 
     #[tokio::test]
-    async fn test_resolve_owner_with_param() {
-        let server = make_server(None, 30);
-        let result = server.resolve_owner(Some("my-org"));
-        assert_eq!(result.unwrap(), "my-org");
+    async fn test_list_pull_review_comments_includes_thread_resolution() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/pulls/5/comments"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"id": 10, "path": "src/lib.rs", "line": 4, "side": "RIGHT", "body": "nit"},
+                {"id": 11, "path": "src/lib.rs", "line": 4, "in_reply_to_id": 10, "body": "done"},
+                {"id": 12, "path": "README.md", "line": 1, "body": "typo"},
+            ])))
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {"repository": {"pullRequest": {"reviewThreads": {
+                    "pageInfo": {"hasNextPage": false, "endCursor": null},
+                    "nodes": [
+                        {"isResolved": true, "isOutdated": false,
+                         "comments": {"nodes": [{"databaseId": 10}, {"databaseId": 11}]}},
+                        {"isResolved": false, "isOutdated": true,
+                         "comments": {"nodes": [{"databaseId": 12}]}},
+                    ]
+                }}}}
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .list_pull_review_comments(Parameters(PullReviewCommentsParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                pr_number: 5,
+                path: Some("src/**".to_string()),
+            }))
+            .await
+            .unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        let body: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["resolution_known"], true);
+        assert_eq!(body["comments"][0]["resolved"], true);
+        assert_eq!(body["comments"][1]["in_reply_to"], 10);
     }
 
     #[tokio::test]
-    async fn test_resolve_owner_with_default() {
-        let server = make_server(Some("default-org".to_string()), 30);
-        let result = server.resolve_owner(None);
-        assert_eq!(result.unwrap(), "default-org");
+    async fn test_graphql_errors_are_reported() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": null,
+                "errors": [{"message": "Could not resolve to a Repository"}]
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        match server
+            .graphql("{ viewer { login } }", serde_json::json!({}))
+            .await
+        {
+            Err(McpGithubError::GraphQL(msg)) => {
+                assert!(msg.contains("Could not resolve to a Repository"))
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
     }
 
     #[tokio::test]
-    async fn test_resolve_owner_param_overrides_default() {
-        let server = make_server(Some("default-org".to_string()), 30);
-        let result = server.resolve_owner(Some("explicit-org"));
-        assert_eq!(result.unwrap(), "explicit-org");
+    async fn test_graphql_query_returns_partial_data_and_gates_mutations() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {"viewer": {"login": "octocat", "secret": null}},
+                "errors": [{"type": "FORBIDDEN", "message": "secret is not accessible"}]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .graphql_query(Parameters(GraphqlParams {
+                query: "{ viewer { login secret } }".to_string(),
+                variables: None,
+            }))
+            .await
+            .unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(body["data"]["viewer"]["login"], "octocat");
+        assert_eq!(body["errors"][0]["type"], "FORBIDDEN");
+
+        let mutation = server
+            .graphql_query(Parameters(GraphqlParams {
+                query: "mutation { addStar(input: {starrableId: \"x\"}) { clientMutationId } }"
+                    .to_string(),
+                variables: None,
+            }))
+            .await;
+        assert!(mutation.unwrap_err().message.contains("--allow-writes"));
     }
 
     #[tokio::test]
-    async fn test_resolve_owner_missing() {
-        let server = make_server(None, 30);
-        let result = server.resolve_owner(None);
-        assert!(result.is_err());
+    async fn test_graphql_not_found_maps_to_not_found() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": null,
+                "errors": [{"type": "NOT_FOUND", "message": "Could not resolve to a Repository"}]
+            })))
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+
+        let result = server
+            .graphql(
+                "{ repository(owner: \"o\", name: \"x\") { id } }",
+                serde_json::json!({}),
+            )
+            .await;
+        assert!(matches!(result, Err(McpGithubError::NotFound(_))));
     }
 
-    #[tokio::test]
-    async fn test_capped_per_page_default() {
-        let server = make_server(None, 30);
-        assert_eq!(server.capped_per_page(None), 30);
+    #[test]
+    fn test_repo_from_api_url() {
+        assert_eq!(
+            repo_from_api_url("https://api.github.com/repos/o/r").as_deref(),
+            Some("o/r")
+        );
+        assert_eq!(
+            repo_from_api_url("https://ghe.example.com/api/v3/repos/o/r/issues/1").as_deref(),
+            Some("o/r")
+        );
+        assert!(repo_from_api_url("https://api.github.com/users/o").is_none());
     }
 
-    #[tokio::test]
-    async fn test_capped_per_page_explicit() {
-        let server = make_server(None, 30);
-        assert_eq!(server.capped_per_page(Some(50)), 50);
+    #[test]
+    fn test_parse_issue_state() {
+        assert!(matches!(
+            parse_issue_state("open"),
+            Ok(octocrab::models::IssueState::Open)
+        ));
+        assert!(matches!(
+            parse_issue_state("closed"),
+            Ok(octocrab::models::IssueState::Closed)
+        ));
+        assert!(parse_issue_state("all").is_err());
     }
 
-    #[tokio::test]
-    async fn test_capped_per_page_caps_at_100() {
-        let server = make_server(None, 30);
-        assert_eq!(server.capped_per_page(Some(200)), 100);
-        assert_eq!(server.capped_per_page(Some(1000)), 100);
+    #[test]
+    fn test_parse_state_reason() {
+        assert!(parse_state_reason("completed").is_ok());
+        assert!(parse_state_reason("not_planned").is_ok());
+        assert!(parse_state_reason("wontfix").is_err());
+    }
+
+    #[test]
+    fn test_split_log_timestamp() {
+        let (ts, rest) = split_log_timestamp("2024-05-01T12:00:03.1234567Z Running tests");
+        assert_eq!(ts, Some("2024-05-01T12:00:03.1234567Z"));
+        assert_eq!(rest, "Running tests");
+
+        let (ts, rest) = split_log_timestamp("no timestamp here");
+        assert_eq!(ts, None);
+        assert_eq!(rest, "no timestamp here");
+    }
+
+    #[test]
+    fn test_step_log_lines_filters_by_time() {
+        let log = "2024-05-01T12:00:01.0000000Z setup\n\
+                   2024-05-01T12:00:05.5000000Z test start\n\
+                   2024-05-01T12:00:09.9000000Z test failed\n\
+                   2024-05-01T12:00:10.1000000Z cleanup";
+        let lines = step_log_lines(log, "2024-05-01T12:00:05Z", "2024-05-01T12:00:09Z");
+        assert_eq!(lines, vec!["test start", "test failed"]);
     }
 
     #[tokio::test]
-    async fn test_capped_per_page_max_results_capped() {
-        // Even if max_results is set high, it should be capped at 100
-        let server = make_server(None, 500);
-        assert_eq!(server.capped_per_page(None), 100);
+    async fn test_get_job_logs_follows_redirect_and_classifies_failures() {
+        let mock = MockServer::start().await;
+        for id in [7, 8] {
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/o/r/actions/jobs/{}", id)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "name": "test", "conclusion": "failure", "steps": []
+                })))
+                .mount(&mock)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/actions/jobs/7/logs"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("location", format!("{}/download/7.txt", mock.uri())),
+            )
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/download/7.txt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "2024-05-01T10:00:00.0000000Z setup\n2024-05-01T10:00:01.0000000Z boom\n",
+            ))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/o/r/actions/jobs/8/logs"))
+            .respond_with(
+                ResponseTemplate::new(410).set_body_json(serde_json::json!({"message": "Gone"})),
+            )
+            .mount(&mock)
+            .await;
+        let server = server_for(&mock);
+        let logs = |job_id| {
+            server.get_job_logs(Parameters(JobLogsParams {
+                owner: Some("o".to_string()),
+                repo: "r".to_string(),
+                job_id,
+                step: None,
+                start_line: None,
+                max_lines: None,
+            }))
+        };
+
+        let result = logs(7).await.unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(body["log"], "setup\nboom");
+        assert_eq!(body["log_truncated"], false);
+
+        let (log, truncated) = server
+            .download_log("/repos/o/r/actions/jobs/7/logs", 40)
+            .await
+            .unwrap();
+        assert!(truncated);
+        assert_eq!(log, "2024-05-01T10:00:01.0000000Z boom\n");
+
+        let err = logs(8).await.unwrap_err();
+        assert_eq!(err.data.unwrap()["code"], "not_found");
+        assert!(err.message.contains("job 8 in o/r"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_line_window_tail() {
+        let lines = vec!["a", "b", "c", "d", "e"];
+        let (first, window) = line_window(&lines, None, 2);
+        assert_eq!(first, 4);
+        assert_eq!(window, &["d", "e"]);
+
+        let (first, window) = line_window(&lines, None, 10);
+        assert_eq!(first, 1);
+        assert_eq!(window.len(), 5);
+    }
+
+    #[test]
+    fn test_line_window_from_start() {
+        let lines = vec!["a", "b", "c", "d", "e"];
+        let (first, window) = line_window(&lines, Some(2), 2);
+        assert_eq!(first, 2);
+        assert_eq!(window, &["b", "c"]);
+
+        let (_, window) = line_window(&lines, Some(99), 2);
+        assert!(window.is_empty());
+
+        let (first, window) = line_window(&lines, Some(2), usize::MAX);
+        assert_eq!(first, 2);
+        assert_eq!(window, &["b", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn test_truncate_utf8() {
+        assert_eq!(truncate_utf8("hello", 10), "hello");
+        assert_eq!(truncate_utf8("hello", 3), "hel");
+        assert_eq!(truncate_utf8("hello", 0), "");
+        // 'é' is two bytes; never split it
+        assert_eq!(truncate_utf8("héllo", 2), "h");
+    }
+
+    #[test]
+    fn test_contents_route() {
+        assert_eq!(contents_route("o", "r", "", None), "/repos/o/r/contents");
+        assert_eq!(
+            contents_route("o", "r", "src/lib", Some("main")),
+            "/repos/o/r/contents/src/lib?ref=main"
+        );
+        assert_eq!(
+            contents_route("o", "r", "docs/my file.md", Some("feature/x")),
+            "/repos/o/r/contents/docs/my%20file.md?ref=feature%2Fx"
+        );
+    }
+
+    #[test]
+    fn test_tree_entries_prefix_glob_and_limit() {
+        let tree = serde_json::json!({
+            "tree": [
+                {"path": "lib.rs", "type": "blob", "size": 10, "sha": "a"},
+                {"path": "server", "type": "tree", "sha": "b"},
+                {"path": "server/mod.rs", "type": "blob", "size": 20, "sha": "c"},
+                {"path": "README.md", "type": "blob", "size": 5, "sha": "d"},
+            ]
+        });
+
+        let (entries, limited) = tree_entries(&tree, "src", None, 10);
+        assert!(!limited);
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0]["path"], "src/lib.rs");
+        assert_eq!(entries[0]["type"], "file");
+        assert_eq!(entries[1]["type"], "dir");
+
+        let glob = compile_glob("**/*.rs", "pattern").unwrap();
+        let (entries, _) = tree_entries(&tree, "src", Some(&glob), 10);
+        let paths: Vec<&str> = entries
+            .iter()
+            .map(|e| e["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(paths, vec!["src/lib.rs", "src/server/mod.rs"]);
+
+        let (entries, limited) = tree_entries(&tree, "", None, 2);
+        assert_eq!(entries.len(), 2);
+        assert!(limited);
+
+        let (_, limited) = tree_entries(&tree, "", Some(&glob), 2);
+        assert!(!limited);
+    }
+
+    #[test]
+    fn test_compile_glob() {
+        let m = compile_glob("src/**/*.rs", "path").unwrap();
+        assert!(m.is_match("src/server.rs"));
+        assert!(m.is_match("src/api/mod.rs"));
+        assert!(!m.is_match("README.md"));
+        assert!(compile_glob("src/[", "path").is_err());
     }
 
     #[test]
@@ -0,0 +1,266 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// A group of related tools that can be enabled together with `--toolsets`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Toolset {
+    /// Repository metadata, branches, commits, files, and releases.
+    Repos,
+    /// Issues, plus the issue write tools when writes are allowed.
+    Issues,
+    /// Pull requests, their files, reviews, and review comments.
+    Pulls,
+    /// GitHub Actions runs, jobs, logs, and commit checks.
+    Actions,
+    /// Code, issue, repository, commit, and user search.
+    Search,
+    /// Raw GraphQL queries.
+    Graphql,
+}
+
+impl Toolset {
+    pub const ALL: [Toolset; 6] = [
+        Toolset::Repos,
+        Toolset::Issues,
+        Toolset::Pulls,
+        Toolset::Actions,
+        Toolset::Search,
+        Toolset::Graphql,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Toolset::Repos => "repos",
+            Toolset::Issues => "issues",
+            Toolset::Pulls => "pulls",
+            Toolset::Actions => "actions",
+            Toolset::Search => "search",
+            Toolset::Graphql => "graphql",
+        }
+    }
+}
+
+impl fmt::Display for Toolset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Toolset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Toolset::ALL
+            .into_iter()
+            .find(|t| t.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Toolset::ALL.iter().map(|t| t.name()).collect();
+                format!(
+                    "unknown toolset '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Every tool the server can register: its toolset (`None` for tools that
+/// are always available) and the phrase used for it in the server
+/// instructions. Listed in the order the instructions mention them.
+pub const TOOLS: &[(&str, Option<Toolset>, &str)] = &[
+    ("list_repos", Some(Toolset::Repos), "list repositories"),
+    ("get_repo", Some(Toolset::Repos), "repository details"),
+    ("list_issues", Some(Toolset::Issues), "list issues"),
+    (
+        "get_issue",
+        Some(Toolset::Issues),
+        "an issue with its comments",
+    ),
+    ("create_issue", Some(Toolset::Issues), "open an issue"),
+    (
+        "update_issue",
+        Some(Toolset::Issues),
+        "edit, close, or reopen an issue",
+    ),
+    (
+        "add_issue_comment",
+        Some(Toolset::Issues),
+        "comment on an issue or PR",
+    ),
+    ("list_pulls", Some(Toolset::Pulls), "list pull requests"),
+    (
+        "get_pull",
+        Some(Toolset::Pulls),
+        "a PR with diff stats and review verdicts",
+    ),
+    ("list_pull_files", Some(Toolset::Pulls), "PR diffs"),
+    ("list_pull_reviews", Some(Toolset::Pulls), "PR reviews"),
+    (
+        "list_pull_review_comments",
+        Some(Toolset::Pulls),
+        "inline review comments",
+    ),
+    ("search_code", Some(Toolset::Search), "search code"),
+    (
+        "search_issues",
+        Some(Toolset::Search),
+        "search issues and PRs",
+    ),
+    (
+        "search_repositories",
+        Some(Toolset::Search),
+        "find repositories",
+    ),
+    ("search_commits", Some(Toolset::Search), "find commits"),
+    (
+        "search_users",
+        Some(Toolset::Search),
+        "find users and organizations",
+    ),
+    (
+        "get_checks",
+        Some(Toolset::Actions),
+        "whether a ref or PR is green",
+    ),
+    ("list_actions_runs", Some(Toolset::Actions), "CI/CD runs"),
+    (
+        "list_run_jobs",
+        Some(Toolset::Actions),
+        "jobs and steps of a run",
+    ),
+    (
+        "get_job_logs",
+        Some(Toolset::Actions),
+        "logs of a failed job",
+    ),
+    ("list_commits", Some(Toolset::Repos), "commit history"),
+    ("get_commit", Some(Toolset::Repos), "a commit with its diff"),
+    ("compare_refs", Some(Toolset::Repos), "diff two refs"),
+    ("list_branches", Some(Toolset::Repos), "branches"),
+    (
+        "list_directory",
+        Some(Toolset::Repos),
+        "a directory listing",
+    ),
+    ("get_tree", Some(Toolset::Repos), "the recursive file tree"),
+    ("get_file_contents", Some(Toolset::Repos), "read a file"),
+    ("get_blame", Some(Toolset::Repos), "line history of a file"),
+    ("list_releases", Some(Toolset::Repos), "releases"),
+    ("list_tags", Some(Toolset::Repos), "tags"),
+    ("graphql_query", Some(Toolset::Graphql), "GraphQL-only data"),
+    ("get_rate_limit", None, "remaining API quota"),
+];
+
+/// Every prompt the server can register and the tools it gathers its data
+/// with. A prompt is only offered while all of its tools are registered.
+pub const PROMPTS: &[(&str, &[&str])] = &[
+    ("review_pull_request", &["get_pull", "list_pull_files"]),
+    ("triage_issue", &["get_issue"]),
+    (
+        "summarize_ci_failure",
+        &["list_actions_runs", "list_run_jobs"],
+    ),
+    ("draft_release_notes", &["list_releases", "list_commits"]),
+];
+
+/// The tools `prompt` relies on; empty if it is not a known prompt.
+pub fn prompt_tools(prompt: &str) -> &'static [&'static str] {
+    PROMPTS
+        .iter()
+        .find(|(name, _)| *name == prompt)
+        .map_or(&[], |(_, tools)| *tools)
+}
+
+/// The toolset a tool belongs to, or `None` if it is not a known tool.
+pub fn toolset_of(tool: &str) -> Option<Option<Toolset>> {
+    TOOLS
+        .iter()
+        .find(|(name, _, _)| *name == tool)
+        .map(|(_, toolset, _)| *toolset)
+}
+
+/// Check that every name is a tool the server knows about.
+pub fn check_tool_names<'a>(names: impl IntoIterator<Item = &'a String>) -> Result<(), String> {
+    for name in names {
+        if toolset_of(name).is_none() {
+            return Err(format!("unknown tool '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+/// Server instructions describing the tools in `enabled` and the prompts in
+/// `prompts`, in [`TOOLS`] and [`PROMPTS`] order.
+pub fn instructions(enabled: &[&str], prompts: &[&str]) -> String {
+    let tools: Vec<String> = TOOLS
+        .iter()
+        .filter(|(name, _, _)| enabled.contains(name))
+        .map(|(name, _, hint)| format!("{} ({})", name, hint))
+        .collect();
+    let mut text = "GitHub server.".to_string();
+    if !tools.is_empty() {
+        text.push_str(&format!(" Tools: {}.", tools.join(", ")));
+    }
+    text.push_str(
+        " Repository files are also available as resources at \
+         github://{owner}/{repo}/blob/{ref}/{path}.",
+    );
+    let prompts: Vec<&str> = PROMPTS
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| prompts.contains(name))
+        .collect();
+    if !prompts.is_empty() {
+        text.push_str(&format!(" Prompts: {}.", prompts.join(", ")));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toolset() {
+        assert_eq!("pulls".parse::<Toolset>(), Ok(Toolset::Pulls));
+        let err = "wiki".parse::<Toolset>().unwrap_err();
+        assert!(err.contains("unknown toolset 'wiki'"), "{}", err);
+        assert!(err.contains("repos, issues"), "{}", err);
+    }
+
+    #[test]
+    fn test_toolset_of() {
+        assert_eq!(toolset_of("get_pull"), Some(Some(Toolset::Pulls)));
+        assert_eq!(toolset_of("get_rate_limit"), Some(None));
+        assert_eq!(toolset_of("delete_repo"), None);
+        assert!(check_tool_names(&["get_tree".to_string()]).is_ok());
+        assert!(check_tool_names(&["get_trees".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_instructions_only_mention_enabled_tools() {
+        let text = instructions(&["get_pull", "get_rate_limit"], &["triage_issue"]);
+        assert!(text.contains("get_pull (a PR with"), "{}", text);
+        assert!(text.contains("get_rate_limit"), "{}", text);
+        assert!(!text.contains("list_issues"), "{}", text);
+        assert!(text.contains("Prompts: triage_issue."), "{}", text);
+        assert!(!text.contains("review_pull_request"), "{}", text);
+        assert!(!instructions(&[], &[]).contains("Tools:"));
+        assert!(!instructions(&[], &[]).contains("Prompts:"));
+    }
+
+    #[test]
+    fn test_prompt_tools_are_known() {
+        for (prompt, tools) in PROMPTS {
+            assert!(!tools.is_empty(), "{}", prompt);
+            assert!(
+                check_tool_names(&tools.iter().map(|t| t.to_string()).collect::<Vec<_>>()).is_ok()
+            );
+        }
+        assert_eq!(prompt_tools("triage_issue"), ["get_issue"]);
+        assert!(prompt_tools("unknown").is_empty());
+    }
+}